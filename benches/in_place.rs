@@ -0,0 +1,114 @@
+//! Compares the in-place `is_occupied` and `apply_then_restore` against the
+//! previous move-out-and-back implementation and `RefCell<Option<T>>`.
+//!
+//! Run with `cargo bench --bench in_place`.
+
+use std::cell::{Cell, RefCell};
+use std::hint::black_box;
+use std::time::Instant;
+
+use cellopt::CellOpt;
+
+const ITERS: u32 = 1_000_000;
+
+#[derive(Clone)]
+struct Big([u8; 4096]);
+
+/// The implementation `CellOpt` used before values were read in place.
+struct MoveOut<T> {
+    slot: Cell<Option<T>>,
+}
+
+impl<T> MoveOut<T> {
+    #[inline(never)]
+    fn is_occupied(&self) -> bool {
+        match self.slot.take() {
+            Some(value) => {
+                self.slot.set(Some(value));
+                true
+            }
+            None => false,
+        }
+    }
+
+    #[inline(never)]
+    fn apply_then_restore<U>(&self, f: impl FnOnce(&T) -> U) -> Option<U> {
+        let value = self.slot.take()?;
+        let u = f(&value);
+        self.slot.set(Some(value));
+        Some(u)
+    }
+}
+
+// Each operation sits behind its own non-inlined function so that the
+// optimizer cannot elide the moves across the benchmark loop.
+#[inline(never)]
+fn cellopt_is_occupied(cell: &CellOpt<Big>) -> bool {
+    cell.is_occupied()
+}
+
+#[inline(never)]
+fn cellopt_apply(cell: &CellOpt<Big>) -> u8 {
+    cell.apply_then_restore(|big| black_box(big).0[100])
+        .unwrap()
+}
+
+#[inline(never)]
+fn ref_cell_is_some(cell: &RefCell<Option<Big>>) -> bool {
+    cell.borrow().is_some()
+}
+
+#[inline(never)]
+fn ref_cell_apply(cell: &RefCell<Option<Big>>) -> u8 {
+    cell.borrow()
+        .as_ref()
+        .map(|big| black_box(big).0[100])
+        .unwrap()
+}
+
+fn bench(name: &str, mut f: impl FnMut()) {
+    let start = Instant::now();
+    for _ in 0..ITERS {
+        f();
+    }
+    let elapsed = start.elapsed();
+    println!(
+        "{:<40} {:>8.2} ns/iter",
+        name,
+        elapsed.as_nanos() as f64 / ITERS as f64
+    );
+}
+
+fn main() {
+    let value = Big([1; 4096]);
+
+    let cell = CellOpt::new(value.clone());
+    bench("CellOpt::is_occupied", || {
+        black_box(cellopt_is_occupied(black_box(&cell)));
+    });
+    bench("CellOpt::apply_then_restore", || {
+        black_box(cellopt_apply(black_box(&cell)));
+    });
+
+    let move_out = MoveOut {
+        slot: Cell::new(Some(value.clone())),
+    };
+    bench("move-out is_occupied", || {
+        black_box(black_box(&move_out).is_occupied());
+    });
+    bench("move-out apply_then_restore", || {
+        black_box(
+            black_box(&move_out)
+                .apply_then_restore(|big| black_box(big).0[100])
+                .unwrap(),
+        );
+    });
+
+    let ref_cell = RefCell::new(Some(value));
+    bench("RefCell<Option<T>> is_some", || {
+        black_box(ref_cell_is_some(black_box(&ref_cell)));
+    });
+    bench("RefCell<Option<T>> borrow", || {
+        black_box(ref_cell_apply(black_box(&ref_cell)));
+    });
+}
@@ -0,0 +1,94 @@
+use core::fmt;
+use core::ops::Deref;
+
+use crate::{CellOpt, Error, InsertErr};
+
+/// A fixed number of `CellOpt` slots, addressed by index.
+///
+/// Derefs to `[CellOpt<T>]`, so every `CellOpt` method is available per slot
+/// and the helpers in `cellopt::slice` apply to the whole array. Methods that
+/// take an index panic if it is out of bounds.
+pub struct CellOptArray<T, const N: usize> {
+    cells: [CellOpt<T>; N],
+}
+
+impl<T, const N: usize> CellOptArray<T, N> {
+    /// An array of empty slots, usable in constants and `thread_local!`.
+    pub const fn new() -> Self {
+        CellOptArray {
+            cells: [const { CellOpt::empty() }; N],
+        }
+    }
+
+    pub fn insert_at(&self, index: usize, value: T) -> Result<(), InsertErr<T>> {
+        self.cells[index].insert(value)
+    }
+
+    pub fn take_at(&self, index: usize) -> Result<T, Error> {
+        self.cells[index].take()
+    }
+
+    /// The index of the first slot a value can be inserted into. Lent and
+    /// poisoned slots are skipped.
+    pub fn first_empty(&self) -> Option<usize> {
+        self.cells
+            .iter()
+            .position(|cell| cell.occupancy() == Ok(false))
+    }
+
+    /// Inserts `value` into the first empty slot and returns its index. Fails
+    /// with `Error::Occupied` if there is none.
+    pub fn insert_anywhere(&self, value: T) -> Result<usize, InsertErr<T>> {
+        let Some(index) = self.first_empty() else {
+            return Err(InsertErr {
+                insert_try: value,
+                err: Error::Occupied,
+            });
+        };
+        self.insert_at(index, value).map(|()| index)
+    }
+
+    /// The number of slots holding a value, including lent ones.
+    pub fn occupied_count(&self) -> usize {
+        self.cells.iter().filter(|cell| cell.is_occupied()).count()
+    }
+
+    /// Indices of the slots holding a value, including lent ones, in order.
+    pub fn occupied_indices(&self) -> impl Iterator<Item = usize> + '_ {
+        self.cells
+            .iter()
+            .enumerate()
+            .filter(|(_, cell)| cell.is_occupied())
+            .map(|(index, _)| index)
+    }
+
+    pub fn into_inner(self) -> [CellOpt<T>; N] {
+        self.cells
+    }
+}
+
+impl<T, const N: usize> Default for CellOptArray<T, N> {
+    fn default() -> Self {
+        CellOptArray::new()
+    }
+}
+
+impl<T, const N: usize> From<[CellOpt<T>; N]> for CellOptArray<T, N> {
+    fn from(cells: [CellOpt<T>; N]) -> Self {
+        CellOptArray { cells }
+    }
+}
+
+impl<T, const N: usize> Deref for CellOptArray<T, N> {
+    type Target = [CellOpt<T>];
+
+    fn deref(&self) -> &[CellOpt<T>] {
+        &self.cells
+    }
+}
+
+impl<T: fmt::Debug, const N: usize> fmt::Debug for CellOptArray<T, N> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("CellOptArray").field(&self.cells).finish()
+    }
+}
@@ -0,0 +1,129 @@
+use alloc::boxed::Box;
+use core::fmt;
+use core::ptr;
+use core::sync::atomic::{AtomicPtr, Ordering};
+
+use crate::{Error, InsertErr};
+
+/// A lock-free slot holding an `Option<Box<T>>`, for handing boxed values
+/// between threads. A null pointer means the slot is empty.
+pub struct AtomicCellOpt<T> {
+    ptr: AtomicPtr<T>,
+}
+
+// Safety: the slot only ever hands out ownership of the whole box, never a
+// reference into it, so sharing the slot only requires `T: Send`.
+unsafe impl<T: Send> Send for AtomicCellOpt<T> {}
+unsafe impl<T: Send> Sync for AtomicCellOpt<T> {}
+
+impl<T> Default for AtomicCellOpt<T> {
+    fn default() -> Self {
+        AtomicCellOpt {
+            ptr: AtomicPtr::new(ptr::null_mut()),
+        }
+    }
+}
+
+impl<T> fmt::Debug for AtomicCellOpt<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_occupied() {
+            write!(f, "AtomicCellOpt(<occupied>)")
+        } else {
+            write!(f, "AtomicCellOpt(<empty>)")
+        }
+    }
+}
+
+impl<T> Drop for AtomicCellOpt<T> {
+    fn drop(&mut self) {
+        let ptr = *self.ptr.get_mut();
+        if !ptr.is_null() {
+            // Safety: a non-null pointer always came from `Box::into_raw` and
+            // is owned by the slot.
+            drop(unsafe { Box::from_raw(ptr) });
+        }
+    }
+}
+
+#[inline]
+fn into_ptr<T>(value: Option<Box<T>>) -> *mut T {
+    value.map_or(ptr::null_mut(), Box::into_raw)
+}
+
+/// Safety: `ptr` must be null or come from `Box::into_raw`, and the caller
+/// must own it.
+#[inline]
+unsafe fn from_ptr<T>(ptr: *mut T) -> Option<Box<T>> {
+    if ptr.is_null() {
+        None
+    } else {
+        Some(Box::from_raw(ptr))
+    }
+}
+
+impl<T> AtomicCellOpt<T> {
+    #[inline]
+    pub fn new(value: Box<T>) -> Self {
+        Self {
+            ptr: AtomicPtr::new(Box::into_raw(value)),
+        }
+    }
+
+    /// Takes the box out, leaving the slot empty.
+    #[inline]
+    pub fn take(&self) -> Result<Box<T>, Error> {
+        self.swap(None).ok_or(Error::Empty)
+    }
+
+    #[inline]
+    pub fn force_take(&self) -> Box<T> {
+        self.take().unwrap()
+    }
+
+    /// Stores `value` if the slot is empty. Release on success so that the
+    /// thread which takes the box sees its contents.
+    #[inline]
+    pub fn insert(&self, value: Box<T>) -> Result<(), InsertErr<Box<T>>> {
+        let new = Box::into_raw(value);
+        match self
+            .ptr
+            .compare_exchange(ptr::null_mut(), new, Ordering::Release, Ordering::Relaxed)
+        {
+            Ok(_) => Ok(()),
+            Err(_) => Err(InsertErr {
+                // Safety: the exchange failed, so `new` was never published.
+                insert_try: unsafe { Box::from_raw(new) },
+                err: Error::Occupied,
+            }),
+        }
+    }
+
+    /// Stores `value` and returns the previous contents. Acquire to see the
+    /// contents of the box being taken, release to publish the one stored.
+    #[inline]
+    pub fn swap(&self, value: Option<Box<T>>) -> Option<Box<T>> {
+        let old = self.ptr.swap(into_ptr(value), Ordering::AcqRel);
+        // Safety: the swap transferred ownership of `old` to this thread.
+        unsafe { from_ptr(old) }
+    }
+
+    /// Stores `value`, dropping any previous value.
+    #[inline]
+    pub fn overwrite(&self, value: Box<T>) {
+        drop(self.swap(Some(value)));
+    }
+
+    /// The answer may be stale by the time it is used. Relaxed is enough
+    /// because the pointer is never dereferenced.
+    #[inline]
+    pub fn is_occupied(&self) -> bool {
+        !self.ptr.load(Ordering::Relaxed).is_null()
+    }
+
+    #[inline]
+    pub fn into_inner(mut self) -> Option<Box<T>> {
+        let ptr = core::mem::replace(self.ptr.get_mut(), ptr::null_mut());
+        // Safety: `self` owned the pointer and no longer refers to it.
+        unsafe { from_ptr(ptr) }
+    }
+}
@@ -0,0 +1,383 @@
+use alloc::boxed::Box;
+use core::cell::Cell;
+use core::cmp::Ordering;
+use core::fmt;
+use core::hash::{Hash, Hasher};
+use core::mem::{self, MaybeUninit};
+
+use crate::{CellOpt, Error, InsertErr, TakeGuard};
+
+/// A `CellOpt` that keeps its value boxed, so that taking, restoring and
+/// updating a large `T` moves a pointer rather than the value. The allocation
+/// is kept across take/insert cycles: when a value is taken out its box is
+/// kept as a spare and reused by the next insert.
+///
+/// Has the same API as `CellOpt`, apart from `scoped`, which it lacks, and
+/// `as_option_mut`, which exposes the boxes.
+pub struct CellOptBox<T> {
+    cell: CellOpt<Box<T>>,
+    spare: Cell<Option<Box<MaybeUninit<T>>>>,
+}
+
+impl<T> Default for CellOptBox<T> {
+    fn default() -> Self {
+        CellOptBox {
+            cell: CellOpt::default(),
+            spare: Cell::new(None),
+        }
+    }
+}
+
+/// Panics if the value is lent out, like `CellOpt`'s `Clone`.
+impl<T: Clone> Clone for CellOptBox<T> {
+    fn clone(&self) -> Self {
+        CellOptBox {
+            cell: self.cell.clone(),
+            spare: Cell::new(None),
+        }
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for CellOptBox<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.cell.fmt_as("CellOptBox", f)
+    }
+}
+
+// Comparing boxes compares their contents, so these agree with `CellOpt<T>`,
+// including the panic on a cell whose value is lent out.
+impl<T: PartialEq> PartialEq for CellOptBox<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.cell == other.cell
+    }
+}
+
+impl<T: Eq> Eq for CellOptBox<T> {}
+
+impl<T: PartialOrd> PartialOrd for CellOptBox<T> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        self.cell.partial_cmp(&other.cell)
+    }
+}
+
+impl<T: Ord> Ord for CellOptBox<T> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.cell.cmp(&other.cell)
+    }
+}
+
+impl<T: Hash> Hash for CellOptBox<T> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.cell.hash(state)
+    }
+}
+
+impl<T> From<T> for CellOptBox<T> {
+    #[inline]
+    fn from(value: T) -> Self {
+        CellOptBox::new(value)
+    }
+}
+
+impl<T> From<Option<T>> for CellOptBox<T> {
+    #[inline]
+    fn from(value: Option<T>) -> Self {
+        CellOptBox {
+            cell: CellOpt::from(value.map(Box::new)),
+            spare: Cell::new(None),
+        }
+    }
+}
+
+impl<T> From<CellOptBox<T>> for Option<T> {
+    #[inline]
+    fn from(cell: CellOptBox<T>) -> Self {
+        cell.into_inner()
+    }
+}
+
+/// Moves the value out of its box, keeping the allocation.
+#[inline]
+fn into_parts<T>(boxed: Box<T>) -> (T, Box<MaybeUninit<T>>) {
+    // Safety: `MaybeUninit<T>` has the same layout as `T`.
+    let allocation = unsafe { Box::from_raw(Box::into_raw(boxed).cast::<MaybeUninit<T>>()) };
+    // Safety: the allocation still holds the initialized value, and it is
+    // treated as uninitialized from here on.
+    let value = unsafe { allocation.assume_init_read() };
+    (value, allocation)
+}
+
+#[inline]
+fn refill<T>(mut allocation: Box<MaybeUninit<T>>, value: T) -> Box<T> {
+    allocation.write(value);
+    // Safety: just initialized.
+    unsafe { allocation.assume_init() }
+}
+
+impl<T> CellOptBox<T> {
+    #[inline]
+    pub fn new(value: T) -> Self {
+        Self {
+            cell: CellOpt::new(Box::new(value)),
+            spare: Cell::new(None),
+        }
+    }
+
+    #[inline]
+    pub const fn empty() -> Self {
+        Self {
+            cell: CellOpt::empty(),
+            spare: Cell::new(None),
+        }
+    }
+
+    /// Boxes `value`, reusing the spare allocation if there is one.
+    #[inline]
+    fn boxed(&self, value: T) -> Box<T> {
+        match self.spare.take() {
+            Some(allocation) => refill(allocation, value),
+            None => Box::new(value),
+        }
+    }
+
+    /// Unboxes `boxed`, keeping its allocation as the spare.
+    #[inline]
+    fn unbox(&self, boxed: Box<T>) -> T {
+        let (value, allocation) = into_parts(boxed);
+        self.spare.set(Some(allocation));
+        value
+    }
+
+    #[inline]
+    fn unbox_err(&self, err: InsertErr<Box<T>>) -> InsertErr<T> {
+        InsertErr {
+            insert_try: self.unbox(err.insert_try),
+            err: err.err,
+        }
+    }
+
+    #[inline]
+    pub fn apply_then_restore<U, F: FnMut(&T) -> U>(&self, mut f: F) -> Result<U, Error> {
+        self.cell.apply_then_restore(|boxed| f(boxed))
+    }
+
+    #[inline]
+    pub fn apply_then_restore_once<U, F: FnOnce(&T) -> U>(&self, f: F) -> Result<U, Error> {
+        self.cell.apply_then_restore_once(|boxed| f(boxed))
+    }
+
+    /// `CellOpt::lend`. The guard holds the box, so `keep` returns a
+    /// `Box<T>`; its allocation is not kept as the spare.
+    #[inline]
+    pub fn lend(&self) -> Result<TakeGuard<'_, Box<T>>, Error> {
+        self.cell.lend()
+    }
+
+    #[inline]
+    pub fn apply_and_update<F: Fn(T) -> T>(&self, f: F) -> Result<(), Error> {
+        self.update(|value| (f(value), ()))
+    }
+
+    /// `CellOpt::update`, reusing the box for the new value. If `f` panics
+    /// the allocation is freed and the cell is poisoned.
+    #[inline]
+    pub fn update<U, F: FnOnce(T) -> (T, U)>(&self, f: F) -> Result<U, Error> {
+        self.cell.update(|boxed| {
+            let (value, allocation) = into_parts(boxed);
+            let (value, u) = f(value);
+            (refill(allocation, value), u)
+        })
+    }
+
+    #[inline]
+    pub fn try_update<E, F>(&self, f: F) -> Result<(), E>
+    where
+        E: From<Error>,
+        F: FnOnce(T) -> Result<T, (T, E)>,
+    {
+        self.cell.try_update(|boxed| {
+            let (value, allocation) = into_parts(boxed);
+            match f(value) {
+                Ok(value) => Ok(refill(allocation, value)),
+                Err((value, err)) => Err((refill(allocation, value), err)),
+            }
+        })
+    }
+
+    #[inline]
+    pub fn insert(&self, value: T) -> Result<(), InsertErr<T>> {
+        if let Err(err) = self.cell.vacancy() {
+            return Err(InsertErr {
+                insert_try: value,
+                err,
+            });
+        }
+        self.cell
+            .insert(self.boxed(value))
+            .map_err(|err| self.unbox_err(err))
+    }
+
+    #[inline]
+    pub fn force_take(&self) -> T {
+        self.take().unwrap()
+    }
+
+    #[inline]
+    pub fn take(&self) -> Result<T, Error> {
+        self.cell.take().map(|boxed| self.unbox(boxed))
+    }
+
+    /// Takes the value out together with its box.
+    #[inline]
+    pub fn take_boxed(&self) -> Result<Box<T>, Error> {
+        self.cell.take()
+    }
+
+    #[inline]
+    pub fn is_occupied(&self) -> bool {
+        self.cell.is_occupied()
+    }
+
+    /// Stores `value`, writing it into the existing box if the cell is
+    /// occupied. The previous value is dropped once the new one is in place.
+    #[inline]
+    pub fn overwrite(&self, value: T) -> Result<(), InsertErr<T>> {
+        self.replace(value).map(drop)
+    }
+
+    /// Stores `boxed` as is, dropping any previous value.
+    #[inline]
+    pub fn overwrite_boxed(&self, boxed: Box<T>) -> Result<(), InsertErr<Box<T>>> {
+        self.cell.overwrite(boxed)
+    }
+
+    #[inline]
+    pub fn is_poisoned(&self) -> bool {
+        self.cell.is_poisoned()
+    }
+
+    #[inline]
+    pub fn clear_poison(&self) {
+        self.cell.clear_poison()
+    }
+
+    #[inline]
+    pub fn into_inner(self) -> Option<T> {
+        self.cell.into_inner().map(|boxed| *boxed)
+    }
+
+    #[inline]
+    pub fn get_mut(&mut self) -> Option<&mut T> {
+        self.cell.get_mut().map(|boxed| &mut **boxed)
+    }
+
+    /// `CellOpt::as_option_mut`, giving access to the box itself.
+    #[inline]
+    pub fn as_option_mut(&mut self) -> &mut Option<Box<T>> {
+        self.cell.as_option_mut()
+    }
+
+    #[inline]
+    pub fn clone_inner(&self) -> Result<T, Error>
+    where
+        T: Clone,
+    {
+        self.apply_then_restore_once(T::clone)
+    }
+
+    /// `CellOpt::replace`, writing `value` into the existing box if the cell
+    /// is occupied.
+    #[inline]
+    pub fn replace(&self, value: T) -> Result<Option<T>, InsertErr<T>> {
+        match self.cell.lend() {
+            Ok(mut guard) => Ok(Some(mem::replace(&mut **guard, value))),
+            Err(Error::Borrowed) => Err(InsertErr {
+                insert_try: value,
+                err: Error::Borrowed,
+            }),
+            Err(_) => self
+                .cell
+                .overwrite(self.boxed(value))
+                .map(|()| None)
+                .map_err(|err| self.unbox_err(err)),
+        }
+    }
+
+    #[inline]
+    pub fn take_if<P: FnOnce(&T) -> bool>(&self, predicate: P) -> Result<Option<T>, Error> {
+        let taken = self.cell.take_if(|boxed| predicate(boxed))?;
+        Ok(taken.map(|boxed| self.unbox(boxed)))
+    }
+
+    #[inline]
+    pub fn is_some_and<F: FnOnce(&T) -> bool>(&self, f: F) -> bool {
+        self.cell.is_some_and(|boxed| f(boxed))
+    }
+
+    #[inline]
+    pub fn insert_with<F: FnOnce() -> T>(&self, f: F) -> Result<(), Error> {
+        self.cell.vacancy()?;
+        self.insert(f())?;
+        Ok(())
+    }
+
+    #[inline]
+    pub fn get_or_insert_with<U, I, F>(&self, init: I, f: F) -> Result<U, Error>
+    where
+        I: FnOnce() -> T,
+        F: FnOnce(&T) -> U,
+    {
+        match self.insert_with(init) {
+            Ok(()) | Err(Error::Occupied) => self.apply_then_restore_once(f),
+            Err(err) => Err(err),
+        }
+    }
+
+    #[inline]
+    pub fn map_in_place<U, F: FnOnce(&mut T) -> U>(&self, f: F) -> Result<U, Error> {
+        self.cell.map_in_place(|boxed| f(boxed))
+    }
+
+    #[inline]
+    pub fn filter_in_place<P: FnOnce(&T) -> bool>(&self, predicate: P) -> Result<(), Error> {
+        self.take_if(|value| !predicate(value))?;
+        Ok(())
+    }
+
+    #[inline]
+    pub fn zip_with<U, R, F>(&self, other: &CellOptBox<U>, f: F) -> Result<R, Error>
+    where
+        F: FnOnce(&T, &U) -> R,
+    {
+        self.cell.zip_with(&other.cell, |t, u| f(t, u))
+    }
+
+    /// `CellOpt::swap`, exchanging the boxes. Spare allocations stay put.
+    #[inline]
+    pub fn swap(&self, other: &CellOptBox<T>) -> Result<(), Error> {
+        self.cell.swap(&other.cell)
+    }
+
+    /// `CellOpt::move_to`, moving the box.
+    #[inline]
+    pub fn move_to(&self, dst: &CellOptBox<T>) -> Result<(), Error> {
+        self.cell.move_to(&dst.cell)
+    }
+
+    #[inline]
+    pub fn take_from(&self, src: &CellOptBox<T>) -> Result<(), Error> {
+        src.move_to(self)
+    }
+
+    #[inline]
+    pub fn xor_insert(&self, value: T) -> Result<Option<(T, T)>, InsertErr<T>> {
+        match self.take() {
+            Ok(old) => Ok(Some((old, value))),
+            Err(Error::Empty) => self.insert(value).map(|()| None),
+            Err(err) => Err(InsertErr {
+                insert_try: value,
+                err,
+            }),
+        }
+    }
+}
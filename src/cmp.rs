@@ -0,0 +1,56 @@
+//! Comparison and hashing, with the same results as for `Option<T>`.
+//!
+//! Values are read in place. Like `RefCell`, comparing or hashing a cell
+//! whose value is lent out panics. A poisoned cell holds no value and
+//! compares as `None`. A cell compared with itself reads its value once and
+//! compares it with itself, so `a == a` behaves as it does for `Option`.
+
+use core::cmp::Ordering;
+use core::hash::{Hash, Hasher};
+use core::ptr;
+
+use crate::{CellOpt, Error};
+
+impl<T> CellOpt<T> {
+    fn with_option<U>(&self, f: impl FnOnce(Option<&T>) -> U) -> U {
+        match self.lend_in_place() {
+            Ok(lend) => f(Some(lend.get())),
+            Err(Error::Borrowed) => panic!("CellOpt compared while its value is lent out"),
+            Err(_) => f(None),
+        }
+    }
+
+    fn with_options<U>(&self, other: &Self, f: impl FnOnce(Option<&T>, Option<&T>) -> U) -> U {
+        if ptr::eq(self, other) {
+            self.with_option(|value| f(value, value))
+        } else {
+            self.with_option(|a| other.with_option(|b| f(a, b)))
+        }
+    }
+}
+
+impl<T: PartialEq> PartialEq for CellOpt<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.with_options(other, |a, b| a.eq(&b))
+    }
+}
+
+impl<T: Eq> Eq for CellOpt<T> {}
+
+impl<T: PartialOrd> PartialOrd for CellOpt<T> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        self.with_options(other, |a, b| a.partial_cmp(&b))
+    }
+}
+
+impl<T: Ord> Ord for CellOpt<T> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.with_options(other, |a, b| a.cmp(&b))
+    }
+}
+
+impl<T: Hash> Hash for CellOpt<T> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.with_option(|value| value.hash(state))
+    }
+}
@@ -0,0 +1,52 @@
+use core::fmt;
+use core::ops::{Deref, DerefMut};
+
+use crate::Lend;
+
+/// A value lent out of a `CellOpt` by `CellOpt::lend`. It is written back
+/// into the cell when the guard is dropped, including while unwinding, unless
+/// `keep` or `consume` is called first.
+pub struct TakeGuard<'a, T> {
+    lend: Lend<'a, T>,
+}
+
+impl<'a, T> TakeGuard<'a, T> {
+    #[inline]
+    pub(crate) fn new(lend: Lend<'a, T>) -> Self {
+        TakeGuard { lend }
+    }
+
+    /// Takes ownership of the value, leaving the cell empty.
+    #[inline]
+    pub fn keep(self) -> T {
+        self.lend.release()
+    }
+
+    /// Drops the value, leaving the cell empty.
+    #[inline]
+    pub fn consume(self) {
+        drop(self.keep());
+    }
+}
+
+impl<T> Deref for TakeGuard<'_, T> {
+    type Target = T;
+
+    #[inline]
+    fn deref(&self) -> &T {
+        self.lend.get()
+    }
+}
+
+impl<T> DerefMut for TakeGuard<'_, T> {
+    #[inline]
+    fn deref_mut(&mut self) -> &mut T {
+        self.lend.get_mut()
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for TakeGuard<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("TakeGuard").field(&**self).finish()
+    }
+}
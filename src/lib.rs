@@ -1,80 +1,429 @@
-use std::cell::Cell;
-use std::fmt;
+#![cfg_attr(not(feature = "std"), no_std)]
 
+#[cfg(feature = "alloc")]
+extern crate alloc;
+
+use core::cell::Cell;
+use core::fmt;
+
+mod array;
+#[cfg(all(feature = "alloc", target_has_atomic = "ptr"))]
+mod atomic;
+#[cfg(feature = "alloc")]
+mod boxed;
+mod cmp;
+mod guard;
+#[cfg(feature = "alloc")]
+pub mod oneshot;
+#[cfg(feature = "alloc")]
+pub mod pool;
+#[cfg(feature = "alloc")]
+mod rc;
+#[cfg(feature = "serde")]
+mod serde_impls;
+#[cfg(feature = "alloc")]
+pub mod signal;
+#[cfg(all(feature = "alloc", target_has_atomic = "ptr"))]
+pub mod slab;
+pub mod slice;
+#[cfg(feature = "std")]
+mod sync;
+#[cfg(feature = "alloc")]
+pub mod txn;
+#[cfg(feature = "alloc")]
+pub mod watch;
+
+pub use array::CellOptArray;
+#[cfg(all(feature = "alloc", target_has_atomic = "ptr"))]
+pub use atomic::AtomicCellOpt;
+#[cfg(feature = "alloc")]
+pub use boxed::CellOptBox;
+pub use guard::TakeGuard;
+#[cfg(feature = "alloc")]
+pub use rc::{RcCellOpt, WeakCellOpt};
+#[cfg(feature = "std")]
+pub use sync::SyncCellOpt;
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Status {
+    Idle,
+    /// The value is lent out, either in place or moved out by an update.
+    Lent,
+    /// An update closure panicked while it owned the value.
+    Poisoned,
+}
+
+// While `status` is `Lent`, `slot` is only touched through the `Lend` or
+// `Restore` that set it. Every other access checks `status` first, so the
+// references a `Lend` creates from `Cell::as_ptr` never alias a read or write.
 pub struct CellOpt<T> {
+    status: Cell<Status>,
     slot: Cell<Option<T>>,
 }
 
 impl<T> Default for CellOpt<T> {
     fn default() -> Self {
+        CellOpt::empty()
+    }
+}
+
+impl<T> From<T> for CellOpt<T> {
+    #[inline]
+    fn from(value: T) -> Self {
+        CellOpt::new(value)
+    }
+}
+
+impl<T> From<Option<T>> for CellOpt<T> {
+    #[inline]
+    fn from(value: Option<T>) -> Self {
         CellOpt {
-            slot: Cell::new(None),
+            status: Cell::new(Status::Idle),
+            slot: Cell::new(value),
         }
     }
 }
 
+impl<T> From<CellOpt<T>> for Option<T> {
+    #[inline]
+    fn from(cell: CellOpt<T>) -> Self {
+        cell.into_inner()
+    }
+}
+
+/// Clones the value in place. Like `RefCell`, cloning a cell whose value is
+/// lent out panics; this includes a derived `Clone` on a parent that is
+/// cloned from inside `apply_then_restore`. A poisoned cell clones as
+/// poisoned.
 impl<T: Clone> Clone for CellOpt<T> {
     fn clone(&self) -> Self {
-        self.apply_then_restore(|inner| CellOpt::new(inner.clone()))
-            .unwrap_or_default()
+        match self.apply_then_restore(|inner| CellOpt::new(inner.clone())) {
+            Ok(cell) => cell,
+            Err(Error::Borrowed) => panic!("CellOpt cloned while its value is lent out"),
+            Err(Error::Poisoned) => CellOpt {
+                status: Cell::new(Status::Poisoned),
+                slot: Cell::new(None),
+            },
+            Err(_) => CellOpt::default(),
+        }
+    }
+}
+
+/// Formats a cell as `name(Some(..))` or `name(None)`, like the `Option` it
+/// stands for, or as `name(<in use>)` / `name(<poisoned>)` when the value
+/// cannot be read.
+fn debug_cell<T: fmt::Debug>(
+    f: &mut fmt::Formatter<'_>,
+    name: &str,
+    value: Result<&T, Error>,
+) -> fmt::Result {
+    let mut tuple = f.debug_tuple(name);
+    match value {
+        Ok(value) => tuple.field(&Some(value)),
+        Err(Error::Borrowed) => tuple.field(&format_args!("<in use>")),
+        Err(Error::Poisoned) => tuple.field(&format_args!("<poisoned>")),
+        Err(_) => tuple.field(&None::<T>),
+    };
+    tuple.finish()
+}
+
+impl<T: fmt::Debug> CellOpt<T> {
+    fn fmt_as(&self, name: &str, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let lend = self.lend_in_place();
+        debug_cell(f, name, lend.as_ref().map(Lend::get).map_err(|err| *err))
     }
 }
 
 impl<T: fmt::Debug> fmt::Debug for CellOpt<T> {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> fmt::Result {
-        self.apply_then_restore(|inner| write!(f, "{}", format_args!("Option::Some({:?})", inner)))
-            .unwrap_or_else(|| write!(f, "None"))
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.fmt_as("CellOpt", f)
     }
 }
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Error {
     Occupied,
     Empty,
+    /// The value is lent out to a closure that is still running.
+    Borrowed,
+    /// A closure passed to `apply_and_update` panicked and the value was lost.
+    Poisoned,
+    /// The cell behind a `WeakCellOpt` has been dropped.
+    Dropped,
+    /// The `CellSlab` slot behind a handle was emptied, and may have been
+    /// reused, since the handle was issued.
+    Stale,
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Error::Occupied => "cell is occupied",
+            Error::Empty => "cell is empty",
+            Error::Borrowed => "cell value is lent out",
+            Error::Poisoned => "cell is poisoned by a panicking update",
+            Error::Dropped => "cell has been dropped",
+            Error::Stale => "slab handle is stale",
+        })
+    }
 }
 
+#[cfg(feature = "std")]
+impl std::error::Error for Error {}
+
+/// Returned when a value could not be stored; hands the value back.
 pub struct InsertErr<T> {
     pub insert_try: T,
     pub err: Error,
 }
 
+impl<T> InsertErr<T> {
+    /// Returns the value that could not be inserted.
+    #[inline]
+    pub fn into_inner(self) -> T {
+        self.insert_try
+    }
+
+    #[inline]
+    pub fn error(&self) -> Error {
+        self.err
+    }
+}
+
+// Like `std::sync::mpsc::SendError`, the value is left out so that `T` does
+// not need to be `Debug` for `unwrap` to work.
+impl<T> fmt::Debug for InsertErr<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("InsertErr")
+            .field("err", &self.err)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<T> fmt::Display for InsertErr<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "cannot insert value: {}", self.err)
+    }
+}
+
+#[cfg(feature = "std")]
+impl<T> std::error::Error for InsertErr<T> {}
+
+impl<T> From<InsertErr<T>> for Error {
+    #[inline]
+    fn from(err: InsertErr<T>) -> Self {
+        err.err
+    }
+}
+
+/// Marks a value as lent out in place and ends the lend when dropped,
+/// including while unwinding.
+struct Lend<'a, T> {
+    cell: &'a CellOpt<T>,
+}
+
+impl<T> Drop for Lend<'_, T> {
+    fn drop(&mut self) {
+        self.cell.status.set(Status::Idle);
+    }
+}
+
+impl<T> Lend<'_, T> {
+    #[inline]
+    fn get(&self) -> &T {
+        // Safety: the lend was only created for an occupied slot, and nothing
+        // else accesses the slot while the status is `Lent`.
+        unsafe { (*self.cell.slot.as_ptr()).as_ref().unwrap_unchecked() }
+    }
+
+    #[inline]
+    fn get_mut(&mut self) -> &mut T {
+        // Safety: as in `get`; `&mut self` makes this the only reference.
+        unsafe { (*self.cell.slot.as_ptr()).as_mut().unwrap_unchecked() }
+    }
+
+    /// Ends the lend by taking the value out, leaving the cell empty.
+    #[inline]
+    fn release(self) -> T {
+        let cell = self.cell;
+        drop(self);
+        // Safety: the slot was occupied when the lend started, and only the
+        // lend could have changed it since.
+        unsafe { cell.slot.take().unwrap_unchecked() }
+    }
+}
+
+/// Holds a value that was moved out of a `CellOpt` and puts it back when
+/// dropped, including while unwinding. If no value is held by then, an update
+/// closure panicked and the cell is poisoned.
+struct Restore<'a, T> {
+    cell: &'a CellOpt<T>,
+    value: Option<T>,
+}
+
+impl<T> Drop for Restore<'_, T> {
+    fn drop(&mut self) {
+        match self.value.take() {
+            Some(value) => {
+                self.cell.slot.set(Some(value));
+                self.cell.status.set(Status::Idle);
+            }
+            None => self.cell.status.set(Status::Poisoned),
+        }
+    }
+}
+
+/// Writes a `CellOpt::scoped` cell back into its `Option` when dropped,
+/// including while unwinding.
+struct WriteBack<'a, T> {
+    option: &'a mut Option<T>,
+    cell: CellOpt<T>,
+}
+
+impl<T> Drop for WriteBack<'_, T> {
+    fn drop(&mut self) {
+        // Any lend ended with the closure that borrowed the cell, so the
+        // status can be ignored; a poisoned cell holds `None`.
+        *self.option = self.cell.slot.take();
+    }
+}
+
 impl<T> CellOpt<T> {
     #[inline]
-    pub fn new(value: T) -> Self {
+    pub const fn new(value: T) -> Self {
         Self {
-            slot: Cell::new(value.into()),
+            status: Cell::new(Status::Idle),
+            slot: Cell::new(Some(value)),
         }
     }
 
+    /// An empty cell. Unlike `Default::default`, usable in constants.
     #[inline]
-    pub fn apply_then_restore<U, F: FnMut(&T) -> U>(&self, mut f: F) -> Option<U> {
-        self.take()
-            .map(|t| {
-                let u = f(&t);
-                self.overwrite(t);
-                u
-            })
-            .ok()
+    pub const fn empty() -> Self {
+        Self {
+            status: Cell::new(Status::Idle),
+            slot: Cell::new(None),
+        }
     }
 
     #[inline]
-    pub fn apply_and_update<F: Fn(T) -> T>(&self, f: F) {
-        if let Ok(t) = self.take() {
-            self.overwrite(f(t));
+    fn check(&self) -> Result<(), Error> {
+        match self.status.get() {
+            Status::Idle => Ok(()),
+            Status::Lent => Err(Error::Borrowed),
+            Status::Poisoned => Err(Error::Poisoned),
         }
     }
 
+    /// Whether the slot holds a value, read in place without moving it.
     #[inline]
-    pub fn insert(&self, value: T) -> Result<(), InsertErr<T>> {
-        if self.is_occupied() {
-            Err(InsertErr {
-                insert_try: value,
-                err: Error::Occupied,
-            })
-        } else {
-            self.overwrite(value);
-            Ok(())
+    fn occupancy(&self) -> Result<bool, Error> {
+        self.check()?;
+        // Safety: the cell is idle, so no references into the slot exist.
+        Ok(unsafe { (*self.slot.as_ptr()).is_some() })
+    }
+
+    /// Marks the value as lent without moving it. Reentrant accesses see
+    /// `Error::Borrowed` until the `Lend` is dropped.
+    #[inline]
+    fn lend_in_place(&self) -> Result<Lend<'_, T>, Error> {
+        if !self.occupancy()? {
+            return Err(Error::Empty);
         }
+        self.status.set(Status::Lent);
+        Ok(Lend { cell: self })
+    }
+
+    /// Moves the value out and marks the cell as lent, so that reentrant
+    /// accesses see `Error::Borrowed` until a `Restore` puts it back.
+    #[inline]
+    fn lend_out(&self) -> Result<Restore<'_, T>, Error> {
+        let value = self.take()?;
+        self.status.set(Status::Lent);
+        Ok(Restore {
+            cell: self,
+            value: Some(value),
+        })
+    }
+
+    /// Runs `f` on the stored value and puts it back afterwards. The value is
+    /// put back even if `f` panics.
+    #[inline]
+    pub fn apply_then_restore<U, F: FnMut(&T) -> U>(&self, f: F) -> Result<U, Error> {
+        self.apply_then_restore_once(f)
+    }
+
+    /// Lends the value out until the returned guard is dropped, at which point
+    /// it is put back. Until then other accesses see `Error::Borrowed`.
+    #[inline]
+    pub fn lend(&self) -> Result<TakeGuard<'_, T>, Error> {
+        self.lend_in_place().map(TakeGuard::new)
+    }
+
+    /// `apply_then_restore` for closures that can only be called once.
+    #[inline]
+    pub fn apply_then_restore_once<U, F: FnOnce(&T) -> U>(&self, f: F) -> Result<U, Error> {
+        let lend = self.lend_in_place()?;
+        Ok(f(lend.get()))
+    }
+
+    /// Replaces the stored value with `f` applied to it. `f` owns the value
+    /// while it runs, so if `f` panics the value is dropped during unwinding
+    /// and the cell is poisoned: later accesses report `Error::Poisoned` until
+    /// `clear_poison` or `overwrite` is called. The value is gone for good; a
+    /// poisoned cell holds nothing that could be recovered.
+    #[inline]
+    pub fn apply_and_update<F: Fn(T) -> T>(&self, f: F) -> Result<(), Error> {
+        self.update(|value| (f(value), ()))
+    }
+
+    /// Replaces the stored value with the first element returned by `f` and
+    /// returns the second. Panics poison the cell, as in `apply_and_update`.
+    #[inline]
+    pub fn update<U, F: FnOnce(T) -> (T, U)>(&self, f: F) -> Result<U, Error> {
+        let mut guard = self.lend_out()?;
+        let (value, u) = f(guard.value.take().unwrap());
+        guard.value = Some(value);
+        Ok(u)
+    }
+
+    /// Like `update`, but `f` may fail. On failure `f` hands the value back
+    /// together with its error, the value is stored again and the error is
+    /// returned. Panics poison the cell, as in `apply_and_update`.
+    #[inline]
+    pub fn try_update<E, F>(&self, f: F) -> Result<(), E>
+    where
+        E: From<Error>,
+        F: FnOnce(T) -> Result<T, (T, E)>,
+    {
+        let mut guard = self.lend_out()?;
+        match f(guard.value.take().unwrap()) {
+            Ok(value) => {
+                guard.value = Some(value);
+                Ok(())
+            }
+            Err((value, err)) => {
+                guard.value = Some(value);
+                Err(err)
+            }
+        }
+    }
+
+    #[inline]
+    pub fn insert(&self, value: T) -> Result<(), InsertErr<T>> {
+        let err = match self.occupancy() {
+            Ok(false) => {
+                self.slot.set(Some(value));
+                return Ok(());
+            }
+            Ok(true) => Error::Occupied,
+            Err(err) => err,
+        };
+        Err(InsertErr {
+            insert_try: value,
+            err,
+        })
     }
 
     #[inline]
@@ -84,29 +433,224 @@ impl<T> CellOpt<T> {
 
     #[inline]
     pub fn take(&self) -> Result<T, Error> {
+        self.check()?;
         self.slot.take().ok_or(Error::Empty)
     }
 
+    /// Returns `true` if the cell holds a value, including while that value
+    /// is lent out. The value is not moved.
     #[inline]
     pub fn is_occupied(&self) -> bool {
-        if let Ok(value) = self.take() {
-            self.overwrite(value);
-            true
-        } else {
-            false
+        match self.occupancy() {
+            Ok(occupied) => occupied,
+            Err(err) => err == Error::Borrowed,
         }
     }
 
+    /// Stores `value`, dropping any previous value and clearing poison. Fails
+    /// with `Error::Borrowed` while the value is lent out.
     #[inline]
-    pub fn overwrite(&self, value: T) {
-        self.slot.replace(Some(value));
+    pub fn overwrite(&self, value: T) -> Result<(), InsertErr<T>> {
+        if self.status.get() == Status::Lent {
+            return Err(InsertErr {
+                insert_try: value,
+                err: Error::Borrowed,
+            });
+        }
+        self.status.set(Status::Idle);
+        // The old value is dropped after the new one is in place, so its
+        // destructor sees a consistent cell.
+        drop(self.slot.replace(Some(value)));
+        Ok(())
     }
 
     #[inline]
-    pub fn clone_inner(&self) -> Option<T>
+    pub fn is_poisoned(&self) -> bool {
+        self.status.get() == Status::Poisoned
+    }
+
+    /// Turns a poisoned cell back into an empty one.
+    #[inline]
+    pub fn clear_poison(&self) {
+        if self.is_poisoned() {
+            self.status.set(Status::Idle);
+        }
+    }
+
+    /// Consumes the cell and returns its value. A poisoned cell holds no
+    /// value and returns `None`.
+    #[inline]
+    pub fn into_inner(self) -> Option<T> {
+        self.slot.into_inner()
+    }
+
+    /// Returns a mutable reference to the value. `&mut self` guarantees that
+    /// nothing else is accessing the cell, so no checks are needed.
+    #[inline]
+    pub fn get_mut(&mut self) -> Option<&mut T> {
+        self.slot.get_mut().as_mut()
+    }
+
+    /// Returns a mutable reference to the underlying `Option`. Since anything
+    /// may be written through it, this clears poison.
+    #[inline]
+    pub fn as_option_mut(&mut self) -> &mut Option<T> {
+        *self.status.get_mut() = Status::Idle;
+        self.slot.get_mut()
+    }
+
+    /// The scoped counterpart of `Cell::from_mut`: runs `f` with `option`
+    /// moved into a `CellOpt`, then writes the cell's contents back, even if
+    /// `f` panics. A `&mut Option<T>` cannot be viewed as a `&CellOpt<T>`
+    /// in place, because the cell keeps its lend and poison status next to
+    /// the value. A cell left poisoned writes back `None`.
+    #[inline]
+    pub fn scoped<U, F: FnOnce(&CellOpt<T>) -> U>(option: &mut Option<T>, f: F) -> U {
+        let write_back = WriteBack {
+            cell: CellOpt::from(option.take()),
+            option,
+        };
+        f(&write_back.cell)
+    }
+
+    #[inline]
+    pub fn clone_inner(&self) -> Result<T, Error>
     where
         T: Clone,
     {
         self.apply_then_restore(|inner| inner.clone())
     }
+
+    /// `Ok` if the cell is empty, otherwise the error an insert would report.
+    #[inline]
+    fn vacancy(&self) -> Result<(), Error> {
+        match self.occupancy()? {
+            true => Err(Error::Occupied),
+            false => Ok(()),
+        }
+    }
+
+    /// Stores `value` and returns the previous value, like `Option::replace`.
+    /// A poisoned cell counts as empty.
+    #[inline]
+    pub fn replace(&self, value: T) -> Result<Option<T>, InsertErr<T>> {
+        let old = match self.take() {
+            Ok(old) => Some(old),
+            Err(Error::Borrowed) => {
+                return Err(InsertErr {
+                    insert_try: value,
+                    err: Error::Borrowed,
+                })
+            }
+            Err(_) => None,
+        };
+        self.overwrite(value)?;
+        Ok(old)
+    }
+
+    /// Takes the value out if `predicate` returns `true` for it. `Ok(None)`
+    /// means the predicate rejected the value and it was left in place.
+    #[inline]
+    pub fn take_if<P: FnOnce(&T) -> bool>(&self, predicate: P) -> Result<Option<T>, Error> {
+        if self.apply_then_restore_once(predicate)? {
+            self.take().map(Some)
+        } else {
+            Ok(None)
+        }
+    }
+
+    /// Returns `true` if the cell holds a value for which `f` returns `true`.
+    #[inline]
+    pub fn is_some_and<F: FnOnce(&T) -> bool>(&self, f: F) -> bool {
+        self.apply_then_restore_once(f).unwrap_or(false)
+    }
+
+    /// Stores the result of `f` if the cell is empty. `f` is not called
+    /// otherwise.
+    #[inline]
+    pub fn insert_with<F: FnOnce() -> T>(&self, f: F) -> Result<(), Error> {
+        self.vacancy()?;
+        self.insert(f())?;
+        Ok(())
+    }
+
+    /// Fills an empty cell with the result of `init`, then runs `f` on the
+    /// stored value.
+    #[inline]
+    pub fn get_or_insert_with<U, I, F>(&self, init: I, f: F) -> Result<U, Error>
+    where
+        I: FnOnce() -> T,
+        F: FnOnce(&T) -> U,
+    {
+        match self.insert_with(init) {
+            Ok(()) | Err(Error::Occupied) => self.apply_then_restore_once(f),
+            Err(err) => Err(err),
+        }
+    }
+
+    /// Runs `f` on a mutable reference to the stored value. The value is put
+    /// back even if `f` panics.
+    #[inline]
+    pub fn map_in_place<U, F: FnOnce(&mut T) -> U>(&self, f: F) -> Result<U, Error> {
+        let mut lend = self.lend_in_place()?;
+        Ok(f(lend.get_mut()))
+    }
+
+    /// Drops the stored value unless `predicate` returns `true` for it.
+    #[inline]
+    pub fn filter_in_place<P: FnOnce(&T) -> bool>(&self, predicate: P) -> Result<(), Error> {
+        self.take_if(|value| !predicate(value))?;
+        Ok(())
+    }
+
+    /// Runs `f` on the values of both cells. Fails if either is not occupied,
+    /// including when `other` is `self`.
+    #[inline]
+    pub fn zip_with<U, R, F>(&self, other: &CellOpt<U>, f: F) -> Result<R, Error>
+    where
+        F: FnOnce(&T, &U) -> R,
+    {
+        self.apply_then_restore_once(|t| other.apply_then_restore_once(|u| f(t, u)))?
+    }
+
+    /// Exchanges the contents of two cells, occupied or not. Swapping a cell
+    /// with itself does nothing.
+    #[inline]
+    pub fn swap(&self, other: &CellOpt<T>) -> Result<(), Error> {
+        self.check()?;
+        other.check()?;
+        self.slot.swap(&other.slot);
+        Ok(())
+    }
+
+    /// Moves the value into `dst`. Fails with `Error::Occupied` if `dst`
+    /// holds a value, in which case neither cell is changed.
+    #[inline]
+    pub fn move_to(&self, dst: &CellOpt<T>) -> Result<(), Error> {
+        dst.vacancy()?;
+        let value = self.take()?;
+        dst.slot.set(Some(value));
+        Ok(())
+    }
+
+    /// Moves the value out of `src` into this cell, like `src.move_to(self)`.
+    #[inline]
+    pub fn take_from(&self, src: &CellOpt<T>) -> Result<(), Error> {
+        src.move_to(self)
+    }
+
+    /// Like `Option::xor`: stores `value` if the cell is empty and returns
+    /// `Ok(None)`. If the cell is occupied it is emptied, and both its value
+    /// and `value` are returned.
+    #[inline]
+    pub fn xor_insert(&self, value: T) -> Result<Option<(T, T)>, InsertErr<T>> {
+        match self.take() {
+            Ok(old) => Ok(Some((old, value))),
+            Err(Error::Empty) => self.insert(value).map(|()| None),
+            Err(err) => Err(InsertErr {
+                insert_try: value,
+                err,
+            }),
+        }
+    }
 }
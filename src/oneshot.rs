@@ -0,0 +1,147 @@
+//! A single-use channel for handing one value to a task on the same thread.
+//!
+//! Both ends share one allocation holding the value slot and the receiver's
+//! `Waker`. Dropping the `Sender` without sending cancels the channel.
+
+use alloc::rc::Rc;
+use core::cell::Cell;
+use core::fmt;
+use core::future::Future;
+use core::pin::Pin;
+use core::task::{Context, Poll, Waker};
+
+use crate::CellOpt;
+
+struct Shared<T> {
+    value: CellOpt<T>,
+    waker: CellOpt<Waker>,
+    /// Set when either end is dropped.
+    closed: Cell<bool>,
+}
+
+impl<T> Shared<T> {
+    fn close(&self) {
+        self.closed.set(true);
+        if let Ok(waker) = self.waker.take() {
+            waker.wake();
+        }
+    }
+}
+
+pub struct Sender<T> {
+    shared: Rc<Shared<T>>,
+}
+
+pub struct Receiver<T> {
+    shared: Rc<Shared<T>>,
+}
+
+/// Returned by the `Receiver` when the `Sender` was dropped without sending.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Canceled;
+
+impl fmt::Display for Canceled {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("oneshot sender dropped without sending")
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for Canceled {}
+
+pub fn channel<T>() -> (Sender<T>, Receiver<T>) {
+    let shared = Rc::new(Shared {
+        value: CellOpt::default(),
+        waker: CellOpt::default(),
+        closed: Cell::new(false),
+    });
+    (
+        Sender {
+            shared: Rc::clone(&shared),
+        },
+        Receiver { shared },
+    )
+}
+
+impl<T> Sender<T> {
+    /// Sends `value` and wakes the receiver. Hands the value back if the
+    /// receiver has been dropped.
+    pub fn send(self, value: T) -> Result<(), T> {
+        if self.shared.closed.get() {
+            return Err(value);
+        }
+        self.shared
+            .value
+            .insert(value)
+            .map_err(|err| err.into_inner())?;
+        if let Ok(waker) = self.shared.waker.take() {
+            waker.wake();
+        }
+        Ok(())
+    }
+
+    /// Returns `true` if the receiver has been dropped.
+    pub fn is_canceled(&self) -> bool {
+        self.shared.closed.get()
+    }
+}
+
+impl<T> Drop for Sender<T> {
+    fn drop(&mut self) {
+        self.shared.close();
+    }
+}
+
+impl<T> fmt::Debug for Sender<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Sender")
+            .field("canceled", &self.is_canceled())
+            .finish()
+    }
+}
+
+impl<T> Receiver<T> {
+    /// Returns the value if it has been sent, `Ok(None)` if it may still be.
+    pub fn try_recv(&mut self) -> Result<Option<T>, Canceled> {
+        match self.shared.value.take() {
+            Ok(value) => Ok(Some(value)),
+            Err(_) if self.shared.closed.get() => Err(Canceled),
+            Err(_) => Ok(None),
+        }
+    }
+}
+
+impl<T> Future for Receiver<T> {
+    type Output = Result<T, Canceled>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.get_mut();
+        match this.try_recv() {
+            Ok(Some(value)) => Poll::Ready(Ok(value)),
+            Err(canceled) => Poll::Ready(Err(canceled)),
+            Ok(None) => {
+                let waker = &this.shared.waker;
+                if !waker.is_some_and(|stored| stored.will_wake(cx.waker())) {
+                    // Nothing else touches the waker slot while we poll.
+                    let _ = waker.overwrite(cx.waker().clone());
+                }
+                Poll::Pending
+            }
+        }
+    }
+}
+
+impl<T> Drop for Receiver<T> {
+    fn drop(&mut self) {
+        self.shared.close();
+    }
+}
+
+impl<T> fmt::Debug for Receiver<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Receiver")
+            .field("ready", &self.shared.value.is_occupied())
+            .field("closed", &self.shared.closed.get())
+            .finish()
+    }
+}
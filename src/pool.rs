@@ -0,0 +1,424 @@
+//! Pools of reusable values, kept in `CellOpt` slots.
+//!
+//! A pool owns at most `max_size` values, counting both idle values and
+//! those checked out. Checked-out values are returned when their guard is
+//! dropped, and there is always a free slot for them. Once the pool is at its
+//! maximum size, `checkout_or_create` refuses to create more until a value is
+//! returned or detached.
+
+use alloc::boxed::Box;
+use core::cell::Cell;
+use core::fmt;
+use core::mem;
+use core::ops::{Deref, DerefMut};
+#[cfg(feature = "std")]
+use core::sync::atomic::{AtomicUsize, Ordering};
+#[cfg(feature = "std")]
+use std::thread;
+
+#[cfg(feature = "std")]
+use crate::SyncCellOpt;
+use crate::{CellOpt, Error, InsertErr};
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Stats {
+    /// Checkouts served by an idle value.
+    pub hits: usize,
+    /// Checkouts that found no idle value.
+    pub misses: usize,
+    /// Values currently checked out.
+    pub outstanding: usize,
+}
+
+fn bump(counter: &Cell<usize>) {
+    counter.set(counter.get() + 1);
+}
+
+/// Counts a value that is being created towards a pool's size, and gives the
+/// space back on drop unless the value was created.
+struct Reservation<'a> {
+    live: &'a Cell<usize>,
+}
+
+impl Drop for Reservation<'_> {
+    fn drop(&mut self) {
+        self.live.set(self.live.get() - 1);
+    }
+}
+
+pub struct Pool<T> {
+    slots: Box<[CellOpt<T>]>,
+    hits: Cell<usize>,
+    misses: Cell<usize>,
+    outstanding: Cell<usize>,
+    /// Idle plus checked-out values.
+    live: Cell<usize>,
+}
+
+impl<T> fmt::Debug for Pool<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Pool")
+            .field("max_size", &self.max_size())
+            .field("stats", &self.stats())
+            .finish_non_exhaustive()
+    }
+}
+
+impl<T> Pool<T> {
+    /// An empty pool that owns at most `max_size` values.
+    pub fn new(max_size: usize) -> Self {
+        Pool {
+            slots: (0..max_size).map(|_| CellOpt::empty()).collect(),
+            hits: Cell::new(0),
+            misses: Cell::new(0),
+            outstanding: Cell::new(0),
+            live: Cell::new(0),
+        }
+    }
+
+    pub fn max_size(&self) -> usize {
+        self.slots.len()
+    }
+
+    /// The number of values waiting to be checked out.
+    pub fn idle(&self) -> usize {
+        self.slots.iter().filter(|slot| slot.is_occupied()).count()
+    }
+
+    pub fn stats(&self) -> Stats {
+        Stats {
+            hits: self.hits.get(),
+            misses: self.misses.get(),
+            outstanding: self.outstanding.get(),
+        }
+    }
+
+    /// The number of values the pool owns, idle or checked out.
+    pub fn len(&self) -> usize {
+        self.live.get()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    fn reserve(&self) -> Option<Reservation<'_>> {
+        let live = self.live.get();
+        if live == self.max_size() {
+            return None;
+        }
+        self.live.set(live + 1);
+        Some(Reservation { live: &self.live })
+    }
+
+    /// Puts a value that is already counted in `live` into a free slot.
+    fn store(&self, value: T) {
+        let mut value = value;
+        for slot in self.slots.iter() {
+            match slot.insert(value) {
+                Ok(()) => return,
+                Err(err) => value = err.insert_try,
+            }
+        }
+        unreachable!("pool has a free slot for every value it owns");
+    }
+
+    /// Adds an idle value. Fails with `Error::Occupied` if the pool is at its
+    /// maximum size.
+    pub fn insert(&self, value: T) -> Result<(), InsertErr<T>> {
+        let Some(reservation) = self.reserve() else {
+            return Err(InsertErr {
+                insert_try: value,
+                err: Error::Occupied,
+            });
+        };
+        mem::forget(reservation);
+        self.store(value);
+        Ok(())
+    }
+
+    fn guard(&self, value: T) -> PoolGuard<'_, T> {
+        bump(&self.outstanding);
+        PoolGuard {
+            pool: self,
+            value: Some(value),
+        }
+    }
+
+    /// Checks out an idle value, if there is one.
+    pub fn checkout(&self) -> Option<PoolGuard<'_, T>> {
+        match self.slots.iter().find_map(|slot| slot.take().ok()) {
+            Some(value) => {
+                bump(&self.hits);
+                Some(self.guard(value))
+            }
+            None => {
+                bump(&self.misses);
+                None
+            }
+        }
+    }
+
+    /// Checks out an idle value, or a new one from `factory` if there is
+    /// none. Returns `None` if there is none and the pool is at its maximum
+    /// size.
+    pub fn checkout_or_create<F: FnOnce() -> T>(&self, factory: F) -> Option<PoolGuard<'_, T>> {
+        if let Some(guard) = self.checkout() {
+            return Some(guard);
+        }
+        let reservation = self.reserve()?;
+        let value = factory();
+        mem::forget(reservation);
+        Some(self.guard(value))
+    }
+}
+
+/// A value checked out of a `Pool`, returned to it on drop.
+pub struct PoolGuard<'a, T> {
+    pool: &'a Pool<T>,
+    /// Only `None` once `detach` has taken the value.
+    value: Option<T>,
+}
+
+impl<T> PoolGuard<'_, T> {
+    /// Takes the value for good instead of returning it to the pool, making
+    /// room for a new one.
+    pub fn detach(mut self) -> T {
+        let live = &self.pool.live;
+        live.set(live.get() - 1);
+        self.value.take().unwrap()
+    }
+}
+
+impl<T> Drop for PoolGuard<'_, T> {
+    fn drop(&mut self) {
+        let outstanding = &self.pool.outstanding;
+        outstanding.set(outstanding.get() - 1);
+        if let Some(value) = self.value.take() {
+            self.pool.store(value);
+        }
+    }
+}
+
+impl<T> Deref for PoolGuard<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        self.value.as_ref().unwrap()
+    }
+}
+
+impl<T> DerefMut for PoolGuard<'_, T> {
+    fn deref_mut(&mut self) -> &mut T {
+        self.value.as_mut().unwrap()
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for PoolGuard<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("PoolGuard").field(&**self).finish()
+    }
+}
+
+/// A thread-safe `Pool`, with `SyncCellOpt` slots.
+#[cfg(feature = "std")]
+pub struct SyncPool<T> {
+    slots: Box<[SyncCellOpt<T>]>,
+    hits: AtomicUsize,
+    misses: AtomicUsize,
+    outstanding: AtomicUsize,
+    /// Idle plus checked-out values.
+    live: AtomicUsize,
+}
+
+/// The `SyncPool` counterpart of `Reservation`.
+#[cfg(feature = "std")]
+struct SyncReservation<'a> {
+    live: &'a AtomicUsize,
+}
+
+#[cfg(feature = "std")]
+impl Drop for SyncReservation<'_> {
+    fn drop(&mut self) {
+        self.live.fetch_sub(1, Ordering::Relaxed);
+    }
+}
+
+#[cfg(feature = "std")]
+impl<T> fmt::Debug for SyncPool<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SyncPool")
+            .field("max_size", &self.max_size())
+            .field("stats", &self.stats())
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg(feature = "std")]
+impl<T> SyncPool<T> {
+    /// An empty pool that owns at most `max_size` values.
+    pub fn new(max_size: usize) -> Self {
+        SyncPool {
+            slots: (0..max_size).map(|_| SyncCellOpt::default()).collect(),
+            hits: AtomicUsize::new(0),
+            misses: AtomicUsize::new(0),
+            outstanding: AtomicUsize::new(0),
+            live: AtomicUsize::new(0),
+        }
+    }
+
+    pub fn max_size(&self) -> usize {
+        self.slots.len()
+    }
+
+    /// The number of values waiting to be checked out.
+    pub fn idle(&self) -> usize {
+        self.slots.iter().filter(|slot| slot.is_occupied()).count()
+    }
+
+    /// The counters are read one at a time, so under contention they may not
+    /// all reflect the same moment.
+    pub fn stats(&self) -> Stats {
+        Stats {
+            hits: self.hits.load(Ordering::Relaxed),
+            misses: self.misses.load(Ordering::Relaxed),
+            outstanding: self.outstanding.load(Ordering::Relaxed),
+        }
+    }
+
+    /// The number of values the pool owns, idle or checked out.
+    pub fn len(&self) -> usize {
+        self.live.load(Ordering::Relaxed)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    fn reserve(&self) -> Option<SyncReservation<'_>> {
+        let max_size = self.max_size();
+        self.live
+            .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |live| {
+                (live < max_size).then_some(live + 1)
+            })
+            .ok()?;
+        Some(SyncReservation { live: &self.live })
+    }
+
+    /// Puts a value that is already counted in `live` into a free slot.
+    fn store(&self, value: T) {
+        // Fewer values than slots are stored at any moment, so a free slot
+        // always exists, but other threads may fill and empty slots while
+        // this one scans them.
+        let mut value = value;
+        loop {
+            for slot in self.slots.iter() {
+                match slot.insert(value) {
+                    Ok(()) => return,
+                    Err(err) => value = err.insert_try,
+                }
+            }
+            thread::yield_now();
+        }
+    }
+
+    /// Adds an idle value. Fails with `Error::Occupied` if the pool is at its
+    /// maximum size.
+    pub fn insert(&self, value: T) -> Result<(), InsertErr<T>> {
+        let Some(reservation) = self.reserve() else {
+            return Err(InsertErr {
+                insert_try: value,
+                err: Error::Occupied,
+            });
+        };
+        mem::forget(reservation);
+        self.store(value);
+        Ok(())
+    }
+
+    fn guard(&self, value: T) -> SyncPoolGuard<'_, T> {
+        self.outstanding.fetch_add(1, Ordering::Relaxed);
+        SyncPoolGuard {
+            pool: self,
+            value: Some(value),
+        }
+    }
+
+    /// Checks out an idle value, if there is one.
+    pub fn checkout(&self) -> Option<SyncPoolGuard<'_, T>> {
+        match self.slots.iter().find_map(|slot| slot.take().ok()) {
+            Some(value) => {
+                self.hits.fetch_add(1, Ordering::Relaxed);
+                Some(self.guard(value))
+            }
+            None => {
+                self.misses.fetch_add(1, Ordering::Relaxed);
+                None
+            }
+        }
+    }
+
+    /// Checks out an idle value, or a new one from `factory` if there is
+    /// none. Returns `None` if there is none and the pool is at its maximum
+    /// size.
+    pub fn checkout_or_create<F: FnOnce() -> T>(&self, factory: F) -> Option<SyncPoolGuard<'_, T>> {
+        if let Some(guard) = self.checkout() {
+            return Some(guard);
+        }
+        let reservation = self.reserve()?;
+        let value = factory();
+        mem::forget(reservation);
+        Some(self.guard(value))
+    }
+}
+
+/// A value checked out of a `SyncPool`, returned to it on drop.
+#[cfg(feature = "std")]
+pub struct SyncPoolGuard<'a, T> {
+    pool: &'a SyncPool<T>,
+    /// Only `None` once `detach` has taken the value.
+    value: Option<T>,
+}
+
+#[cfg(feature = "std")]
+impl<T> SyncPoolGuard<'_, T> {
+    /// Takes the value for good instead of returning it to the pool, making
+    /// room for a new one.
+    pub fn detach(mut self) -> T {
+        self.pool.live.fetch_sub(1, Ordering::Relaxed);
+        self.value.take().unwrap()
+    }
+}
+
+#[cfg(feature = "std")]
+impl<T> Drop for SyncPoolGuard<'_, T> {
+    fn drop(&mut self) {
+        self.pool.outstanding.fetch_sub(1, Ordering::Relaxed);
+        if let Some(value) = self.value.take() {
+            self.pool.store(value);
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl<T> Deref for SyncPoolGuard<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        self.value.as_ref().unwrap()
+    }
+}
+
+#[cfg(feature = "std")]
+impl<T> DerefMut for SyncPoolGuard<'_, T> {
+    fn deref_mut(&mut self) -> &mut T {
+        self.value.as_mut().unwrap()
+    }
+}
+
+#[cfg(feature = "std")]
+impl<T: fmt::Debug> fmt::Debug for SyncPoolGuard<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("SyncPoolGuard").field(&**self).finish()
+    }
+}
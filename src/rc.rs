@@ -0,0 +1,300 @@
+use alloc::rc::{Rc, Weak};
+use core::fmt;
+use core::ops::Deref;
+
+use crate::{CellOpt, Error, InsertErr};
+
+/// A shared handle to a `CellOpt`. Cloning the handle shares the slot; the
+/// `CellOpt` API is reached through `Deref`.
+pub struct RcCellOpt<T> {
+    cell: Rc<CellOpt<T>>,
+}
+
+/// A weak handle to the slot of an `RcCellOpt`. Once every `RcCellOpt` for
+/// the slot has been dropped, operations report `Error::Dropped`.
+pub struct WeakCellOpt<T> {
+    cell: Weak<CellOpt<T>>,
+}
+
+impl<T> Clone for RcCellOpt<T> {
+    fn clone(&self) -> Self {
+        RcCellOpt {
+            cell: Rc::clone(&self.cell),
+        }
+    }
+}
+
+impl<T> Default for RcCellOpt<T> {
+    fn default() -> Self {
+        RcCellOpt {
+            cell: Rc::default(),
+        }
+    }
+}
+
+impl<T> From<CellOpt<T>> for RcCellOpt<T> {
+    #[inline]
+    fn from(cell: CellOpt<T>) -> Self {
+        RcCellOpt {
+            cell: Rc::new(cell),
+        }
+    }
+}
+
+impl<T> Deref for RcCellOpt<T> {
+    type Target = CellOpt<T>;
+
+    #[inline]
+    fn deref(&self) -> &CellOpt<T> {
+        &self.cell
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for RcCellOpt<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.cell.fmt_as("RcCellOpt", f)
+    }
+}
+
+impl<T> RcCellOpt<T> {
+    #[inline]
+    pub fn new(value: T) -> Self {
+        CellOpt::new(value).into()
+    }
+
+    #[inline]
+    pub fn downgrade(&self) -> WeakCellOpt<T> {
+        WeakCellOpt {
+            cell: Rc::downgrade(&self.cell),
+        }
+    }
+
+    /// Returns `true` if both handles share the same slot.
+    #[inline]
+    pub fn ptr_eq(&self, other: &Self) -> bool {
+        Rc::ptr_eq(&self.cell, &other.cell)
+    }
+
+    /// The address of the shared slot, for use as an identity key. It is the
+    /// same for every strong and weak handle to the slot.
+    #[inline]
+    pub fn as_ptr(&self) -> *const CellOpt<T> {
+        Rc::as_ptr(&self.cell)
+    }
+}
+
+impl<T> Clone for WeakCellOpt<T> {
+    fn clone(&self) -> Self {
+        WeakCellOpt {
+            cell: Weak::clone(&self.cell),
+        }
+    }
+}
+
+/// A handle that was never attached to a slot and always reports
+/// `Error::Dropped`.
+impl<T> Default for WeakCellOpt<T> {
+    fn default() -> Self {
+        WeakCellOpt { cell: Weak::new() }
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for WeakCellOpt<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.cell.upgrade() {
+            Some(cell) => cell.fmt_as("WeakCellOpt", f),
+            None => f
+                .debug_tuple("WeakCellOpt")
+                .field(&format_args!("<dropped>"))
+                .finish(),
+        }
+    }
+}
+
+#[inline]
+fn dropped<V>(value: V) -> InsertErr<V> {
+    InsertErr {
+        insert_try: value,
+        err: Error::Dropped,
+    }
+}
+
+impl<T> WeakCellOpt<T> {
+    #[inline]
+    pub fn upgrade(&self) -> Option<RcCellOpt<T>> {
+        self.cell.upgrade().map(|cell| RcCellOpt { cell })
+    }
+
+    /// Returns `true` if both handles point to the same slot, or were both
+    /// created by `default`.
+    #[inline]
+    pub fn ptr_eq(&self, other: &Self) -> bool {
+        Weak::ptr_eq(&self.cell, &other.cell)
+    }
+
+    /// See `RcCellOpt::as_ptr`. The address stays valid as a key after the
+    /// slot is dropped, for as long as a weak handle keeps it allocated.
+    #[inline]
+    pub fn as_ptr(&self) -> *const CellOpt<T> {
+        Weak::as_ptr(&self.cell)
+    }
+
+    #[inline]
+    fn cell(&self) -> Result<Rc<CellOpt<T>>, Error> {
+        self.cell.upgrade().ok_or(Error::Dropped)
+    }
+
+    #[inline]
+    pub fn apply_then_restore<U, F: FnMut(&T) -> U>(&self, f: F) -> Result<U, Error> {
+        self.cell()?.apply_then_restore(f)
+    }
+
+    #[inline]
+    pub fn apply_then_restore_once<U, F: FnOnce(&T) -> U>(&self, f: F) -> Result<U, Error> {
+        self.cell()?.apply_then_restore_once(f)
+    }
+
+    #[inline]
+    pub fn apply_and_update<F: Fn(T) -> T>(&self, f: F) -> Result<(), Error> {
+        self.cell()?.apply_and_update(f)
+    }
+
+    #[inline]
+    pub fn update<U, F: FnOnce(T) -> (T, U)>(&self, f: F) -> Result<U, Error> {
+        self.cell()?.update(f)
+    }
+
+    #[inline]
+    pub fn try_update<E, F>(&self, f: F) -> Result<(), E>
+    where
+        E: From<Error>,
+        F: FnOnce(T) -> Result<T, (T, E)>,
+    {
+        self.cell()?.try_update(f)
+    }
+
+    #[inline]
+    pub fn insert(&self, value: T) -> Result<(), InsertErr<T>> {
+        match self.cell() {
+            Ok(cell) => cell.insert(value),
+            Err(_) => Err(dropped(value)),
+        }
+    }
+
+    #[inline]
+    pub fn force_take(&self) -> T {
+        self.take().unwrap()
+    }
+
+    #[inline]
+    pub fn take(&self) -> Result<T, Error> {
+        self.cell()?.take()
+    }
+
+    /// Returns `false` once the slot has been dropped.
+    #[inline]
+    pub fn is_occupied(&self) -> bool {
+        self.cell().is_ok_and(|cell| cell.is_occupied())
+    }
+
+    #[inline]
+    pub fn overwrite(&self, value: T) -> Result<(), InsertErr<T>> {
+        match self.cell() {
+            Ok(cell) => cell.overwrite(value),
+            Err(_) => Err(dropped(value)),
+        }
+    }
+
+    #[inline]
+    pub fn is_poisoned(&self) -> bool {
+        self.cell().is_ok_and(|cell| cell.is_poisoned())
+    }
+
+    #[inline]
+    pub fn clear_poison(&self) {
+        if let Ok(cell) = self.cell() {
+            cell.clear_poison();
+        }
+    }
+
+    #[inline]
+    pub fn clone_inner(&self) -> Result<T, Error>
+    where
+        T: Clone,
+    {
+        self.cell()?.clone_inner()
+    }
+
+    #[inline]
+    pub fn replace(&self, value: T) -> Result<Option<T>, InsertErr<T>> {
+        match self.cell() {
+            Ok(cell) => cell.replace(value),
+            Err(_) => Err(dropped(value)),
+        }
+    }
+
+    #[inline]
+    pub fn take_if<P: FnOnce(&T) -> bool>(&self, predicate: P) -> Result<Option<T>, Error> {
+        self.cell()?.take_if(predicate)
+    }
+
+    #[inline]
+    pub fn is_some_and<F: FnOnce(&T) -> bool>(&self, f: F) -> bool {
+        self.cell().is_ok_and(|cell| cell.is_some_and(f))
+    }
+
+    #[inline]
+    pub fn insert_with<F: FnOnce() -> T>(&self, f: F) -> Result<(), Error> {
+        self.cell()?.insert_with(f)
+    }
+
+    #[inline]
+    pub fn get_or_insert_with<U, I, F>(&self, init: I, f: F) -> Result<U, Error>
+    where
+        I: FnOnce() -> T,
+        F: FnOnce(&T) -> U,
+    {
+        self.cell()?.get_or_insert_with(init, f)
+    }
+
+    #[inline]
+    pub fn map_in_place<U, F: FnOnce(&mut T) -> U>(&self, f: F) -> Result<U, Error> {
+        self.cell()?.map_in_place(f)
+    }
+
+    #[inline]
+    pub fn filter_in_place<P: FnOnce(&T) -> bool>(&self, predicate: P) -> Result<(), Error> {
+        self.cell()?.filter_in_place(predicate)
+    }
+
+    #[inline]
+    pub fn zip_with<U, R, F>(&self, other: &CellOpt<U>, f: F) -> Result<R, Error>
+    where
+        F: FnOnce(&T, &U) -> R,
+    {
+        self.cell()?.zip_with(other, f)
+    }
+
+    #[inline]
+    pub fn swap(&self, other: &CellOpt<T>) -> Result<(), Error> {
+        self.cell()?.swap(other)
+    }
+
+    #[inline]
+    pub fn move_to(&self, dst: &CellOpt<T>) -> Result<(), Error> {
+        self.cell()?.move_to(dst)
+    }
+
+    #[inline]
+    pub fn take_from(&self, src: &CellOpt<T>) -> Result<(), Error> {
+        self.cell()?.take_from(src)
+    }
+
+    #[inline]
+    pub fn xor_insert(&self, value: T) -> Result<Option<(T, T)>, InsertErr<T>> {
+        match self.cell() {
+            Ok(cell) => cell.xor_insert(value),
+            Err(_) => Err(dropped(value)),
+        }
+    }
+}
@@ -0,0 +1,46 @@
+use serde::de::{Deserialize, Deserializer};
+use serde::ser::{Error as _, Serialize, Serializer};
+
+#[cfg(feature = "alloc")]
+use crate::CellOptBox;
+use crate::{CellOpt, Error};
+
+/// Serializes like `Option<T>`. A cell whose value is lent out, or which is
+/// poisoned, cannot be serialized and reports an error rather than `None`.
+impl<T: Serialize> Serialize for CellOpt<T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self.lend() {
+            Ok(value) => serializer.serialize_some(&*value),
+            Err(Error::Empty) => serializer.serialize_none(),
+            Err(err) => Err(S::Error::custom(err)),
+        }
+    }
+}
+
+impl<'de, T: Deserialize<'de>> Deserialize<'de> for CellOpt<T> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        Ok(match Option::deserialize(deserializer)? {
+            Some(value) => CellOpt::new(value),
+            None => CellOpt::default(),
+        })
+    }
+}
+
+/// Serializes like `CellOpt<T>`; the box is transparent.
+#[cfg(feature = "alloc")]
+impl<T: Serialize> Serialize for CellOptBox<T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self.lend() {
+            Ok(boxed) => serializer.serialize_some(&**boxed),
+            Err(Error::Empty) => serializer.serialize_none(),
+            Err(err) => Err(S::Error::custom(err)),
+        }
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl<'de, T: Deserialize<'de>> Deserialize<'de> for CellOptBox<T> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        Option::deserialize(deserializer).map(CellOptBox::from)
+    }
+}
@@ -0,0 +1,374 @@
+//! A `CellOpt` that notifies subscribers when its value changes.
+//!
+//! Callbacks run while the signal is dispatching an event. Mutations made
+//! from inside a callback are not applied reentrantly: `insert`, `overwrite`
+//! and `clear` are queued and applied, each with its own event, once the
+//! current dispatch has finished, and report `Outcome::Queued`. A queued
+//! insert is checked against the state the signal will be in when it is
+//! applied, so it fails right away if that state is occupied. `take` and
+//! `update` need the value right away and report `Error::Borrowed` instead.
+
+use alloc::boxed::Box;
+use alloc::collections::VecDeque;
+use alloc::rc::{Rc, Weak};
+use alloc::vec::Vec;
+use core::cell::Cell;
+use core::fmt;
+use core::mem;
+
+use crate::{CellOpt, Error, InsertErr};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Event<V> {
+    Inserted(V),
+    Overwritten(V),
+    /// Carries the value that was taken out.
+    Taken(V),
+    Updated(V),
+}
+
+impl<V> Event<V> {
+    pub fn value(self) -> V {
+        match self {
+            Event::Inserted(v) | Event::Overwritten(v) | Event::Taken(v) | Event::Updated(v) => v,
+        }
+    }
+
+    pub fn map<W, F: FnOnce(V) -> W>(self, f: F) -> Event<W> {
+        match self {
+            Event::Inserted(v) => Event::Inserted(f(v)),
+            Event::Overwritten(v) => Event::Overwritten(f(v)),
+            Event::Taken(v) => Event::Taken(f(v)),
+            Event::Updated(v) => Event::Updated(f(v)),
+        }
+    }
+}
+
+impl<T: Clone> Event<&T> {
+    pub fn cloned(self) -> Event<T> {
+        self.map(T::clone)
+    }
+}
+
+type Callback<T> = Box<dyn FnMut(Event<&T>)>;
+
+struct Subscribers<T> {
+    list: Cell<Vec<(u64, Callback<T>)>>,
+    /// Subscriptions dropped while `list` was taken out for a dispatch.
+    removed: Cell<Vec<u64>>,
+    dispatching: Cell<bool>,
+    next_id: Cell<u64>,
+}
+
+impl<T> Subscribers<T> {
+    fn remove(&self, id: u64) {
+        let mut list = self.list.take();
+        let removed = list
+            .iter()
+            .position(|(entry, _)| *entry == id)
+            .map(|index| list.remove(index));
+        self.list.set(list);
+        if removed.is_none() && self.dispatching.get() {
+            let mut pending = self.removed.take();
+            pending.push(id);
+            self.removed.set(pending);
+        }
+        // The callback is dropped last, so anything its captures do on drop
+        // sees a consistent list.
+        drop(removed);
+    }
+
+    fn is_removed(&self, id: u64) -> bool {
+        let pending = self.removed.take();
+        let removed = pending.contains(&id);
+        self.removed.set(pending);
+        removed
+    }
+}
+
+/// Ends a dispatch, also while unwinding: puts the callbacks back, together
+/// with any subscribed during the dispatch, minus any unsubscribed.
+struct Dispatch<'a, T> {
+    subscribers: &'a Subscribers<T>,
+    list: Vec<(u64, Callback<T>)>,
+}
+
+impl<T> Drop for Dispatch<'_, T> {
+    fn drop(&mut self) {
+        let subscribers = self.subscribers;
+        let mut list = mem::take(&mut self.list);
+        list.append(&mut subscribers.list.take());
+        let removed = subscribers.removed.take();
+        let (dropped, kept) = list.into_iter().partition(|(id, _)| removed.contains(id));
+        subscribers.list.set(kept);
+        subscribers.dispatching.set(false);
+        drop::<Vec<_>>(dropped);
+    }
+}
+
+/// What a successful mutation did.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Outcome {
+    Applied,
+    /// Called from inside a callback; the mutation runs once the current
+    /// dispatch has finished.
+    Queued,
+}
+
+enum Op<T> {
+    Insert(T),
+    Overwrite(T),
+    Clear,
+}
+
+pub struct Signal<T> {
+    cell: CellOpt<T>,
+    subscribers: Rc<Subscribers<T>>,
+    queue: Cell<VecDeque<Op<T>>>,
+    /// Whether the cell will hold a value once the queue has been applied.
+    projected: Cell<bool>,
+    /// Values of queued operations that could not be applied.
+    rejected: Cell<Vec<T>>,
+}
+
+/// Keeps a callback subscribed until it is dropped.
+#[must_use = "dropping a Subscription unsubscribes its callback"]
+pub struct Subscription<T> {
+    subscribers: Weak<Subscribers<T>>,
+    id: u64,
+}
+
+impl<T> Drop for Subscription<T> {
+    fn drop(&mut self) {
+        if let Some(subscribers) = self.subscribers.upgrade() {
+            subscribers.remove(self.id);
+        }
+    }
+}
+
+impl<T> fmt::Debug for Subscription<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("Subscription").field(&self.id).finish()
+    }
+}
+
+impl<T> Default for Signal<T> {
+    fn default() -> Self {
+        Signal::from(CellOpt::default())
+    }
+}
+
+impl<T> From<CellOpt<T>> for Signal<T> {
+    fn from(cell: CellOpt<T>) -> Self {
+        Signal {
+            cell,
+            subscribers: Rc::new(Subscribers {
+                list: Cell::new(Vec::new()),
+                removed: Cell::new(Vec::new()),
+                dispatching: Cell::new(false),
+                next_id: Cell::new(0),
+            }),
+            queue: Cell::new(VecDeque::new()),
+            projected: Cell::new(false),
+            rejected: Cell::new(Vec::new()),
+        }
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for Signal<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.cell.fmt_as("Signal", f)
+    }
+}
+
+impl<T> Signal<T> {
+    pub fn new(value: T) -> Self {
+        Signal::from(CellOpt::new(value))
+    }
+
+    pub fn subscribe<F: FnMut(Event<&T>) + 'static>(&self, callback: F) -> Subscription<T> {
+        let subscribers = &self.subscribers;
+        let id = subscribers.next_id.get();
+        subscribers.next_id.set(id + 1);
+        let mut list = subscribers.list.take();
+        list.push((id, Box::new(callback)));
+        subscribers.list.set(list);
+        Subscription {
+            subscribers: Rc::downgrade(subscribers),
+            id,
+        }
+    }
+
+    fn dispatching(&self) -> bool {
+        self.subscribers.dispatching.get()
+    }
+
+    fn dispatch(&self, event: Event<&T>) {
+        let queue = self.queue.take();
+        if queue.is_empty() {
+            self.projected.set(!matches!(event, Event::Taken(_)));
+        }
+        self.queue.set(queue);
+        let subscribers = &*self.subscribers;
+        subscribers.dispatching.set(true);
+        let mut dispatch = Dispatch {
+            subscribers,
+            list: subscribers.list.take(),
+        };
+        for (id, callback) in dispatch.list.iter_mut() {
+            if !subscribers.is_removed(*id) {
+                callback(event);
+            }
+        }
+    }
+
+    /// Dispatches an event for the stored value.
+    fn notify(&self, event: fn(&T) -> Event<&T>) {
+        let _ = self
+            .cell
+            .apply_then_restore_once(|value| self.dispatch(event(value)));
+    }
+
+    fn enqueue(&self, op: Op<T>) -> Outcome {
+        self.projected.set(!matches!(op, Op::Clear));
+        let mut queue = self.queue.take();
+        queue.push_back(op);
+        self.queue.set(queue);
+        Outcome::Queued
+    }
+
+    fn reject(&self, err: InsertErr<T>) {
+        let mut rejected = self.rejected.take();
+        rejected.push(err.insert_try);
+        self.rejected.set(rejected);
+    }
+
+    /// Applies operations queued by callbacks. Values that cannot be stored
+    /// are kept for `take_rejected`.
+    fn drain(&self) {
+        if self.dispatching() {
+            return;
+        }
+        loop {
+            let mut queue = self.queue.take();
+            let op = queue.pop_front();
+            self.queue.set(queue);
+            match op {
+                Some(Op::Insert(value)) => {
+                    if let Err(err) = self.insert_now(value) {
+                        self.reject(err);
+                    }
+                }
+                Some(Op::Overwrite(value)) => {
+                    if let Err(err) = self.overwrite_now(value) {
+                        self.reject(err);
+                    }
+                }
+                Some(Op::Clear) => drop(self.take_now()),
+                None => return,
+            }
+        }
+    }
+
+    fn insert_now(&self, value: T) -> Result<(), InsertErr<T>> {
+        self.cell.insert(value)?;
+        self.notify(|value| Event::Inserted(value));
+        Ok(())
+    }
+
+    fn overwrite_now(&self, value: T) -> Result<(), InsertErr<T>> {
+        self.cell.overwrite(value)?;
+        self.notify(|value| Event::Overwritten(value));
+        Ok(())
+    }
+
+    fn take_now(&self) -> Result<T, Error> {
+        let value = self.cell.take()?;
+        self.dispatch(Event::Taken(&value));
+        Ok(value)
+    }
+
+    /// Inserts `value` and notifies subscribers. From inside a callback the
+    /// insert is queued, unless the signal will be occupied by then.
+    pub fn insert(&self, value: T) -> Result<Outcome, InsertErr<T>> {
+        if self.dispatching() {
+            if self.projected.get() {
+                return Err(InsertErr {
+                    insert_try: value,
+                    err: Error::Occupied,
+                });
+            }
+            return Ok(self.enqueue(Op::Insert(value)));
+        }
+        let result = self.insert_now(value);
+        self.drain();
+        result.map(|()| Outcome::Applied)
+    }
+
+    /// Overwrites the value and notifies subscribers. Queued when called
+    /// from inside a callback.
+    pub fn overwrite(&self, value: T) -> Result<Outcome, InsertErr<T>> {
+        if self.dispatching() {
+            return Ok(self.enqueue(Op::Overwrite(value)));
+        }
+        let result = self.overwrite_now(value);
+        self.drain();
+        result.map(|()| Outcome::Applied)
+    }
+
+    /// Drops the value, if any, notifying subscribers with `Event::Taken`.
+    /// Queued when called from inside a callback.
+    pub fn clear(&self) -> Outcome {
+        if self.dispatching() {
+            return self.enqueue(Op::Clear);
+        }
+        drop(self.take_now());
+        self.drain();
+        Outcome::Applied
+    }
+
+    /// Returns the values of queued inserts and overwrites that could not be
+    /// applied. This only happens when a callback panics, leaving its queued
+    /// operations to run later against a signal that has changed since.
+    pub fn take_rejected(&self) -> Vec<T> {
+        self.rejected.take()
+    }
+
+    /// Takes the value out and notifies subscribers. Fails with
+    /// `Error::Borrowed` from inside a callback.
+    pub fn take(&self) -> Result<T, Error> {
+        if self.dispatching() {
+            return Err(Error::Borrowed);
+        }
+        let result = self.take_now();
+        self.drain();
+        result
+    }
+
+    /// Replaces the value with `f` applied to it and notifies subscribers.
+    /// Fails with `Error::Borrowed` from inside a callback.
+    pub fn update<F: FnOnce(T) -> T>(&self, f: F) -> Result<(), Error> {
+        if self.dispatching() {
+            return Err(Error::Borrowed);
+        }
+        self.cell.update(|value| (f(value), ()))?;
+        self.notify(|value| Event::Updated(value));
+        self.drain();
+        Ok(())
+    }
+
+    pub fn apply_then_restore<U, F: FnOnce(&T) -> U>(&self, f: F) -> Result<U, Error> {
+        self.cell.apply_then_restore_once(f)
+    }
+
+    pub fn is_occupied(&self) -> bool {
+        self.cell.is_occupied()
+    }
+
+    pub fn clone_inner(&self) -> Result<T, Error>
+    where
+        T: Clone,
+    {
+        self.cell.clone_inner()
+    }
+}
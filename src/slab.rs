@@ -0,0 +1,190 @@
+//! A growable collection of `CellOpt` slots addressed by generational
+//! handles.
+
+use alloc::boxed::Box;
+use alloc::vec::Vec;
+use core::cell::Cell;
+use core::fmt;
+use core::ptr::NonNull;
+use core::sync::atomic::{AtomicUsize, Ordering};
+
+use crate::{CellOpt, Error};
+
+const CHUNK: usize = 32;
+
+/// Hands out a distinct id to every slab, so handles can't cross slabs.
+static NEXT_ID: AtomicUsize = AtomicUsize::new(0);
+
+struct Entry<T> {
+    /// Bumped whenever the slot is emptied, so older handles go stale.
+    generation: Cell<u64>,
+    value: CellOpt<T>,
+}
+
+impl<T> Entry<T> {
+    const fn empty() -> Self {
+        Entry {
+            generation: Cell::new(0),
+            value: CellOpt::empty(),
+        }
+    }
+}
+
+/// Identifies a value inserted into a `CellSlab`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Handle {
+    slab: usize,
+    index: usize,
+    generation: u64,
+}
+
+/// Like a `RefCell<Vec<Option<T>>>` registry, but usable entirely through
+/// `&self`: values can be inserted and taken from inside `apply_then_restore`
+/// closures, and a handle whose value was taken reports `Error::Stale` rather
+/// than reaching whatever reuses its slot. Handles from another slab are
+/// stale too.
+pub struct CellSlab<T> {
+    // Slots live in fixed-size chunks that never move or get freed before
+    // the slab is dropped, so references into them stay valid while the
+    // slab grows.
+    chunks: Cell<Vec<NonNull<[Entry<T>; CHUNK]>>>,
+    id: usize,
+    free: Cell<Vec<usize>>,
+    /// The number of slots ever used.
+    used: Cell<usize>,
+}
+
+// Safety: the slab owns its chunks exclusively, like a `Vec<CellOpt<T>>`.
+unsafe impl<T: Send> Send for CellSlab<T> {}
+
+impl<T> Default for CellSlab<T> {
+    fn default() -> Self {
+        CellSlab {
+            chunks: Cell::new(Vec::new()),
+            id: NEXT_ID.fetch_add(1, Ordering::Relaxed),
+            free: Cell::new(Vec::new()),
+            used: Cell::new(0),
+        }
+    }
+}
+
+impl<T> Drop for CellSlab<T> {
+    fn drop(&mut self) {
+        for chunk in self.chunks.get_mut().drain(..) {
+            // Safety: every chunk came from `Box::into_raw` in `grow` and is
+            // freed only here.
+            drop(unsafe { Box::from_raw(chunk.as_ptr()) });
+        }
+    }
+}
+
+impl<T> fmt::Debug for CellSlab<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("CellSlab")
+            .field("len", &self.len())
+            .finish_non_exhaustive()
+    }
+}
+
+impl<T> CellSlab<T> {
+    pub fn new() -> Self {
+        CellSlab::default()
+    }
+
+    /// The slot at `index`, if its chunk has been allocated.
+    fn slot(&self, index: usize) -> Option<&Entry<T>> {
+        let chunks = self.chunks.take();
+        let chunk = chunks.get(index / CHUNK).copied();
+        self.chunks.set(chunks);
+        // Safety: chunks stay allocated, and are only accessed through
+        // shared references, until the slab is dropped.
+        chunk.map(|chunk| unsafe { &(*chunk.as_ptr())[index % CHUNK] })
+    }
+
+    fn entry(&self, handle: Handle) -> Result<&Entry<T>, Error> {
+        if handle.slab != self.id {
+            return Err(Error::Stale);
+        }
+        match self.slot(handle.index) {
+            Some(entry) if entry.generation.get() == handle.generation => Ok(entry),
+            _ => Err(Error::Stale),
+        }
+    }
+
+    fn grow(&self) {
+        let chunk = Box::new([const { Entry::empty() }; CHUNK]);
+        let mut chunks = self.chunks.take();
+        chunks.push(NonNull::from(Box::leak(chunk)));
+        self.chunks.set(chunks);
+    }
+
+    pub fn insert(&self, value: T) -> Handle {
+        let mut free = self.free.take();
+        let reused = free.pop();
+        self.free.set(free);
+        let index = reused.unwrap_or_else(|| {
+            let index = self.used.get();
+            if self.slot(index).is_none() {
+                self.grow();
+            }
+            self.used.set(index + 1);
+            index
+        });
+        let entry = self.slot(index).expect("slot was allocated");
+        // Free slots are always empty and idle: handles to them are stale, so
+        // nothing can be lent out of them.
+        if entry.value.insert(value).is_err() {
+            unreachable!("free slab slot was occupied");
+        }
+        Handle {
+            slab: self.id,
+            index,
+            generation: entry.generation.get(),
+        }
+    }
+
+    /// Takes the value out, freeing its slot for reuse. `handle` and every
+    /// copy of it go stale.
+    pub fn take(&self, handle: Handle) -> Result<T, Error> {
+        let entry = self.entry(handle)?;
+        let value = entry.value.take()?;
+        entry.generation.set(handle.generation + 1);
+        let mut free = self.free.take();
+        free.push(handle.index);
+        self.free.set(free);
+        Ok(value)
+    }
+
+    pub fn apply_then_restore<U, F: FnOnce(&T) -> U>(
+        &self,
+        handle: Handle,
+        f: F,
+    ) -> Result<U, Error> {
+        self.entry(handle)?.value.apply_then_restore_once(f)
+    }
+
+    pub fn map_in_place<U, F: FnOnce(&mut T) -> U>(
+        &self,
+        handle: Handle,
+        f: F,
+    ) -> Result<U, Error> {
+        self.entry(handle)?.value.map_in_place(f)
+    }
+
+    /// Returns `true` if `handle` still refers to a value.
+    pub fn contains(&self, handle: Handle) -> bool {
+        self.entry(handle).is_ok()
+    }
+
+    /// The number of values in the slab.
+    pub fn len(&self) -> usize {
+        let free = self.free.take();
+        let len = self.used.get() - free.len();
+        self.free.set(free);
+        len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
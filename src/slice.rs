@@ -0,0 +1,78 @@
+//! Moving values around within a slice of `CellOpt`s.
+//!
+//! Every cell in the slice must be idle: if any is lent out or poisoned, the
+//! operation fails with that error before anything is moved.
+
+#[cfg(feature = "alloc")]
+use alloc::vec;
+
+use crate::{CellOpt, Error};
+
+fn check_all<T>(cells: &[CellOpt<T>]) -> Result<(), Error> {
+    cells.iter().try_for_each(CellOpt::check)
+}
+
+fn reverse<T>(cells: &[CellOpt<T>]) {
+    let (front, back) = cells.split_at(cells.len() / 2);
+    for (a, b) in front.iter().zip(back.iter().rev()) {
+        a.slot.swap(&b.slot);
+    }
+}
+
+/// Rotates the contents so that the cell at `mid` becomes the first, like
+/// `<[T]>::rotate_left`. Panics if `mid` is greater than the length.
+pub fn rotate_left<T>(cells: &[CellOpt<T>], mid: usize) -> Result<(), Error> {
+    assert!(mid <= cells.len(), "mid out of bounds");
+    check_all(cells)?;
+    let (front, back) = cells.split_at(mid);
+    reverse(front);
+    reverse(back);
+    reverse(cells);
+    Ok(())
+}
+
+/// Rotates the contents so that the last `k` cells come first, like
+/// `<[T]>::rotate_right`. Panics if `k` is greater than the length.
+pub fn rotate_right<T>(cells: &[CellOpt<T>], k: usize) -> Result<(), Error> {
+    assert!(k <= cells.len(), "k out of bounds");
+    rotate_left(cells, cells.len() - k)
+}
+
+/// Exchanges the contents of the cells at `a` and `b`. Panics if either index
+/// is out of bounds.
+pub fn swap_indices<T>(cells: &[CellOpt<T>], a: usize, b: usize) -> Result<(), Error> {
+    cells[a].swap(&cells[b])
+}
+
+/// Reorders the contents so that cell `i` ends up with what cell
+/// `indices[i]` held. Panics if `indices` is not a permutation of
+/// `0..cells.len()`.
+#[cfg(feature = "alloc")]
+pub fn permute<T>(cells: &[CellOpt<T>], indices: &[usize]) -> Result<(), Error> {
+    assert_eq!(indices.len(), cells.len(), "wrong number of indices");
+    let mut seen = vec![false; cells.len()];
+    for &index in indices {
+        assert!(
+            index < cells.len() && !seen[index],
+            "indices are not a permutation"
+        );
+        seen[index] = true;
+    }
+    check_all(cells)?;
+    // Each cycle is walked once, pulling every value into place by swapping
+    // it with the one it replaces. `seen` now marks positions still to visit.
+    for start in 0..cells.len() {
+        if !seen[start] {
+            continue;
+        }
+        seen[start] = false;
+        let mut at = start;
+        while indices[at] != start {
+            let next = indices[at];
+            cells[at].slot.swap(&cells[next].slot);
+            seen[next] = false;
+            at = next;
+        }
+    }
+    Ok(())
+}
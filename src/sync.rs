@@ -0,0 +1,270 @@
+use std::fmt;
+use std::mem;
+use std::sync::{Condvar, Mutex, MutexGuard, PoisonError};
+use std::thread::{self, ThreadId};
+
+use crate::{debug_cell, Error, InsertErr};
+
+#[derive(Default)]
+enum Slot<T> {
+    #[default]
+    Empty,
+    Occupied(T),
+    /// The value has been moved out temporarily by the given thread and will
+    /// be put back.
+    Lent(ThreadId),
+    /// An update closure panicked while it owned the value.
+    Poisoned,
+}
+
+impl<T> Slot<T> {
+    /// The error reported when a value is expected but this slot is found.
+    fn error(&self) -> Error {
+        match self {
+            Slot::Empty => Error::Empty,
+            Slot::Occupied(_) => Error::Occupied,
+            Slot::Lent(_) => Error::Borrowed,
+            Slot::Poisoned => Error::Poisoned,
+        }
+    }
+}
+
+/// A thread-safe `CellOpt`. The lock is only held while the slot itself is
+/// read or written, never while user closures run: `apply_then_restore` and
+/// `apply_and_update` move the value out and mark the slot as lent to the
+/// calling thread. Other threads wait until the value is put back, as they
+/// would for a `Mutex`; only reentrant access from the lending thread sees
+/// `Error::Borrowed`. As with a `Mutex`, two threads that each wait on a cell
+/// the other has lent out deadlock.
+pub struct SyncCellOpt<T> {
+    slot: Mutex<Slot<T>>,
+    /// Signalled whenever a lent value is put back.
+    returned: Condvar,
+}
+
+impl<T> Default for SyncCellOpt<T> {
+    fn default() -> Self {
+        SyncCellOpt {
+            slot: Mutex::new(Slot::Empty),
+            returned: Condvar::new(),
+        }
+    }
+}
+
+/// Clones the value in place. While another thread has the value lent out,
+/// cloning waits for it to come back; cloning a cell whose value is lent out
+/// to the current thread panics. A poisoned cell clones as poisoned.
+impl<T: Clone> Clone for SyncCellOpt<T> {
+    fn clone(&self) -> Self {
+        match self.apply_then_restore(|inner| SyncCellOpt::new(inner.clone())) {
+            Ok(cell) => cell,
+            Err(Error::Borrowed) => panic!("SyncCellOpt cloned while its value is lent out"),
+            Err(Error::Poisoned) => SyncCellOpt {
+                slot: Mutex::new(Slot::Poisoned),
+                returned: Condvar::new(),
+            },
+            Err(_) => SyncCellOpt::default(),
+        }
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for SyncCellOpt<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let lent = self.lend_out();
+        let value = match &lent {
+            Ok(guard) => Ok(guard.value.as_ref().unwrap()),
+            Err(err) => Err(*err),
+        };
+        debug_cell(f, "SyncCellOpt", value)
+    }
+}
+
+/// The `SyncCellOpt` counterpart of `Restore`.
+struct SyncRestore<'a, T> {
+    cell: &'a SyncCellOpt<T>,
+    value: Option<T>,
+}
+
+impl<T> Drop for SyncRestore<'_, T> {
+    fn drop(&mut self) {
+        let slot = match self.value.take() {
+            Some(value) => Slot::Occupied(value),
+            None => Slot::Poisoned,
+        };
+        *self.cell.lock() = slot;
+        self.cell.returned.notify_all();
+    }
+}
+
+impl<T> SyncCellOpt<T> {
+    #[inline]
+    pub fn new(value: T) -> Self {
+        Self {
+            slot: Mutex::new(Slot::Occupied(value)),
+            returned: Condvar::new(),
+        }
+    }
+
+    /// No user code runs while the lock is held, so the mutex can only be
+    /// poisoned by a panic inside the standard library; the slot is still
+    /// consistent in that case.
+    #[inline]
+    fn lock(&self) -> MutexGuard<'_, Slot<T>> {
+        self.slot.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Locks the slot once it is not lent to another thread. Fails with
+    /// `Error::Borrowed` if it is lent to the current one.
+    fn acquire(&self) -> Result<MutexGuard<'_, Slot<T>>, Error> {
+        let current = thread::current().id();
+        let mut slot = self.lock();
+        while let Slot::Lent(owner) = *slot {
+            if owner == current {
+                return Err(Error::Borrowed);
+            }
+            slot = self
+                .returned
+                .wait(slot)
+                .unwrap_or_else(PoisonError::into_inner);
+        }
+        Ok(slot)
+    }
+
+    #[inline]
+    fn lend_out(&self) -> Result<SyncRestore<'_, T>, Error> {
+        let mut slot = self.acquire()?;
+        match mem::replace(&mut *slot, Slot::Lent(thread::current().id())) {
+            Slot::Occupied(value) => Ok(SyncRestore {
+                cell: self,
+                value: Some(value),
+            }),
+            other => {
+                let err = other.error();
+                *slot = other;
+                Err(err)
+            }
+        }
+    }
+
+    /// Runs `f` on the stored value and puts it back afterwards. The value is
+    /// put back even if `f` panics.
+    #[inline]
+    pub fn apply_then_restore<U, F: FnMut(&T) -> U>(&self, mut f: F) -> Result<U, Error> {
+        let guard = self.lend_out()?;
+        Ok(f(guard.value.as_ref().unwrap()))
+    }
+
+    /// Replaces the stored value with `f` applied to it. If `f` panics the
+    /// cell is poisoned and the value is lost, as with
+    /// `CellOpt::apply_and_update`.
+    #[inline]
+    pub fn apply_and_update<F: Fn(T) -> T>(&self, f: F) -> Result<(), Error> {
+        let mut guard = self.lend_out()?;
+        let value = guard.value.take().unwrap();
+        guard.value = Some(f(value));
+        Ok(())
+    }
+
+    #[inline]
+    pub fn insert(&self, value: T) -> Result<(), InsertErr<T>> {
+        let mut slot = match self.acquire() {
+            Ok(slot) => slot,
+            Err(err) => {
+                return Err(InsertErr {
+                    insert_try: value,
+                    err,
+                })
+            }
+        };
+        match &*slot {
+            Slot::Empty => {
+                *slot = Slot::Occupied(value);
+                Ok(())
+            }
+            other => Err(InsertErr {
+                insert_try: value,
+                err: other.error(),
+            }),
+        }
+    }
+
+    #[inline]
+    pub fn force_take(&self) -> T {
+        self.take().unwrap()
+    }
+
+    #[inline]
+    pub fn take(&self) -> Result<T, Error> {
+        let mut slot = self.acquire()?;
+        match mem::take(&mut *slot) {
+            Slot::Occupied(value) => Ok(value),
+            other => {
+                let err = other.error();
+                *slot = other;
+                Err(err)
+            }
+        }
+    }
+
+    /// Returns `true` if the cell holds a value, including while that value
+    /// is lent out.
+    #[inline]
+    pub fn is_occupied(&self) -> bool {
+        matches!(*self.lock(), Slot::Occupied(_) | Slot::Lent(_))
+    }
+
+    /// Stores `value`, dropping any previous value and clearing poison. Fails
+    /// with `Error::Borrowed` while the value is lent out to this thread.
+    #[inline]
+    pub fn overwrite(&self, value: T) -> Result<(), InsertErr<T>> {
+        let mut slot = match self.acquire() {
+            Ok(slot) => slot,
+            Err(err) => {
+                return Err(InsertErr {
+                    insert_try: value,
+                    err,
+                })
+            }
+        };
+        let old = mem::replace(&mut *slot, Slot::Occupied(value));
+        drop(slot);
+        drop(old);
+        Ok(())
+    }
+
+    #[inline]
+    pub fn is_poisoned(&self) -> bool {
+        matches!(*self.lock(), Slot::Poisoned)
+    }
+
+    /// Turns a poisoned cell back into an empty one.
+    #[inline]
+    pub fn clear_poison(&self) {
+        let mut slot = self.lock();
+        if let Slot::Poisoned = *slot {
+            *slot = Slot::Empty;
+        }
+    }
+
+    /// Consumes the cell and returns its value. A poisoned cell holds no
+    /// value and returns `None`.
+    #[inline]
+    pub fn into_inner(self) -> Option<T> {
+        match self
+            .slot
+            .into_inner()
+            .unwrap_or_else(PoisonError::into_inner)
+        {
+            Slot::Occupied(value) => Some(value),
+            _ => None,
+        }
+    }
+
+    #[inline]
+    pub fn clone_inner(&self) -> Result<T, Error>
+    where
+        T: Clone,
+    {
+        self.apply_then_restore(|inner| inner.clone())
+    }
+}
@@ -0,0 +1,249 @@
+//! Moving values between several `CellOpt`s all-or-nothing.
+//!
+//! A `Transaction` records takes and inserts and applies them together on
+//! `commit`. Every cell an operation touches is locked when the operation is
+//! recorded: other code, and any other operation in the same transaction,
+//! sees `Error::Borrowed` for that cell until the transaction is committed or
+//! rolled back. Nothing can change underneath a transaction, so committing
+//! cannot fail, and rolling back only has to unlock the cells.
+//!
+//! A taken value can be read until the transaction ends. To own it
+//! afterwards, turn it into a `Claim`, which receives the value on commit.
+
+use alloc::boxed::Box;
+use alloc::rc::{Rc, Weak};
+use alloc::vec::Vec;
+use core::cell::Cell;
+use core::fmt;
+use core::ops::Deref;
+
+use crate::{CellOpt, Error, InsertErr, Status};
+
+/// Marks a cell as lent for the life of a transaction. Unlike a `Lend`, the
+/// cell may be empty.
+struct Lock<'a, T> {
+    cell: &'a CellOpt<T>,
+}
+
+impl<'a, T> Lock<'a, T> {
+    /// Locks `cell`, which must hold a value if `occupied` and be empty
+    /// otherwise.
+    fn new(cell: &'a CellOpt<T>, occupied: bool) -> Result<Self, Error> {
+        match cell.occupancy()? {
+            true if !occupied => Err(Error::Occupied),
+            false if occupied => Err(Error::Empty),
+            _ => {
+                cell.status.set(Status::Lent);
+                Ok(Lock { cell })
+            }
+        }
+    }
+}
+
+impl<T> Drop for Lock<'_, T> {
+    fn drop(&mut self) {
+        self.cell.status.set(Status::Idle);
+    }
+}
+
+/// Type-erases values that a commit drops, so they can be dropped after
+/// every change has been made.
+trait Discard {}
+
+impl<T> Discard for T {}
+
+trait Entry<'a> {
+    /// Makes the recorded change, returning any value it removed from a cell.
+    /// Must not run user code.
+    fn commit(self: Box<Self>) -> Option<Box<dyn Discard + 'a>>;
+}
+
+struct Take<'a, T> {
+    lock: Lock<'a, T>,
+    claim: Weak<Cell<Option<T>>>,
+}
+
+impl<'a, T: 'a> Entry<'a> for Take<'a, T> {
+    fn commit(self: Box<Self>) -> Option<Box<dyn Discard + 'a>> {
+        let value = self.lock.cell.slot.take();
+        match self.claim.upgrade() {
+            Some(claim) => {
+                claim.set(value);
+                None
+            }
+            None => Some(Box::new(value)),
+        }
+    }
+}
+
+struct Insert<'a, T> {
+    lock: Lock<'a, T>,
+    value: T,
+}
+
+impl<'a, T: 'a> Entry<'a> for Insert<'a, T> {
+    fn commit(self: Box<Self>) -> Option<Box<dyn Discard + 'a>> {
+        let Insert { lock, value } = *self;
+        lock.cell.slot.set(Some(value));
+        None
+    }
+}
+
+struct Transfer<'a, T> {
+    src: Lock<'a, T>,
+    dst: Lock<'a, T>,
+}
+
+impl<'a, T: 'a> Entry<'a> for Transfer<'a, T> {
+    fn commit(self: Box<Self>) -> Option<Box<dyn Discard + 'a>> {
+        self.dst.cell.slot.set(self.src.cell.slot.take());
+        None
+    }
+}
+
+/// A value staged to be taken by `Transaction::take`, readable until the
+/// transaction is committed or rolled back.
+pub struct Taken<'t, T> {
+    value: &'t T,
+    claim: Rc<Cell<Option<T>>>,
+}
+
+impl<T> Deref for Taken<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        self.value
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for Taken<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("Taken").field(self.value).finish()
+    }
+}
+
+impl<T> Taken<'_, T> {
+    /// Keeps the taken value instead of letting the commit drop it.
+    pub fn claim(self) -> Claim<T> {
+        Claim { slot: self.claim }
+    }
+}
+
+/// Receives a taken value when its transaction is committed.
+pub struct Claim<T> {
+    slot: Rc<Cell<Option<T>>>,
+}
+
+impl<T> fmt::Debug for Claim<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Claim").finish_non_exhaustive()
+    }
+}
+
+impl<T> Claim<T> {
+    /// Returns the value once the transaction is committed. `None` if it was
+    /// rolled back or has not been committed yet; a claim redeemed before
+    /// the commit leaves the value to be dropped by it.
+    pub fn into_inner(self) -> Option<T> {
+        self.slot.take()
+    }
+}
+
+/// A set of takes and inserts against any number of `CellOpt`s, which may
+/// hold different types. Each cell can be used by one operation per
+/// transaction. While locked, an empty cell reports `is_occupied` as `true`,
+/// like any other cell in use.
+#[must_use = "dropping a Transaction rolls it back"]
+#[derive(Default)]
+pub struct Transaction<'a> {
+    log: Cell<Vec<Box<dyn Entry<'a> + 'a>>>,
+}
+
+impl fmt::Debug for Transaction<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Transaction")
+            .field("len", &self.len())
+            .finish()
+    }
+}
+
+impl<'a> Transaction<'a> {
+    pub fn new() -> Self {
+        Transaction::default()
+    }
+
+    fn record(&self, entry: Box<dyn Entry<'a> + 'a>) {
+        let mut log = self.log.take();
+        log.push(entry);
+        self.log.set(log);
+    }
+
+    /// Records taking the value out of `cell`, which is emptied on commit.
+    /// The value stays readable through the returned `Taken` until then, and
+    /// is dropped by the commit unless it has been claimed.
+    pub fn take<T: 'a>(&self, cell: &'a CellOpt<T>) -> Result<Taken<'_, T>, Error> {
+        let lock = Lock::new(cell, true)?;
+        // Safety: the cell is occupied and stays locked, so nothing else can
+        // touch the slot, until the transaction is committed or rolled back.
+        // Both need the transaction by value, which ends this borrow.
+        let value = unsafe { (*cell.slot.as_ptr()).as_ref().unwrap_unchecked() };
+        let claim = Rc::new(Cell::new(None));
+        self.record(Box::new(Take {
+            lock,
+            claim: Rc::downgrade(&claim),
+        }));
+        Ok(Taken { value, claim })
+    }
+
+    /// Records inserting `value` into `cell`, which must be empty. The value
+    /// is dropped if the transaction is rolled back.
+    pub fn insert<T: 'a>(&self, cell: &'a CellOpt<T>, value: T) -> Result<(), InsertErr<T>> {
+        match Lock::new(cell, false) {
+            Ok(lock) => {
+                self.record(Box::new(Insert { lock, value }));
+                Ok(())
+            }
+            Err(err) => Err(InsertErr {
+                insert_try: value,
+                err,
+            }),
+        }
+    }
+
+    /// Records moving the value from `src` into `dst`, which must be empty.
+    pub fn transfer<T: 'a>(&self, src: &'a CellOpt<T>, dst: &'a CellOpt<T>) -> Result<(), Error> {
+        let dst = Lock::new(dst, false)?;
+        let src = Lock::new(src, true)?;
+        self.record(Box::new(Transfer { src, dst }));
+        Ok(())
+    }
+
+    /// The number of operations recorded so far.
+    pub fn len(&self) -> usize {
+        let log = self.log.take();
+        let len = log.len();
+        self.log.set(log);
+        len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Applies every recorded operation and unlocks the cells. Claimed values
+    /// are handed to their claims; other taken values are dropped last, once
+    /// every cell is consistent.
+    pub fn commit(self) {
+        let discarded: Vec<_> = self
+            .log
+            .take()
+            .into_iter()
+            .filter_map(|entry| entry.commit())
+            .collect();
+        drop(discarded);
+    }
+
+    /// Unlocks every cell, leaving them as they were. Same as dropping the
+    /// transaction.
+    pub fn rollback(self) {}
+}
@@ -0,0 +1,228 @@
+//! A latest-value channel for tasks on the same thread.
+//!
+//! The `Sender` overwrites the value in a shared `CellOpt` and bumps a
+//! version counter. Each `Receiver` remembers the last version it has seen
+//! and can wait for a newer one with `changed`.
+
+use alloc::rc::Rc;
+use alloc::vec::Vec;
+use core::cell::Cell;
+use core::fmt;
+use core::future::Future;
+use core::pin::Pin;
+use core::task::{Context, Poll, Waker};
+
+use crate::{CellOpt, Error, InsertErr};
+
+struct Shared<T> {
+    value: CellOpt<T>,
+    version: Cell<u64>,
+    wakers: Cell<Vec<Waker>>,
+    /// Set when the sender is dropped.
+    closed: Cell<bool>,
+}
+
+impl<T> Shared<T> {
+    fn wake_all(&self) {
+        for waker in self.wakers.take() {
+            waker.wake();
+        }
+    }
+
+    fn register(&self, waker: &Waker) {
+        let mut wakers = self.wakers.take();
+        if !wakers.iter().any(|stored| stored.will_wake(waker)) {
+            wakers.push(waker.clone());
+        }
+        self.wakers.set(wakers);
+    }
+}
+
+pub struct Sender<T> {
+    shared: Rc<Shared<T>>,
+}
+
+pub struct Receiver<T> {
+    shared: Rc<Shared<T>>,
+    seen: u64,
+}
+
+/// Returned by `Receiver::changed` once the sender has been dropped and every
+/// value it sent has been seen.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Closed;
+
+impl fmt::Display for Closed {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("watch sender dropped")
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for Closed {}
+
+/// Creates a channel holding `initial`, which receivers treat as already
+/// seen.
+pub fn channel<T>(initial: T) -> (Sender<T>, Receiver<T>) {
+    let shared = Rc::new(Shared {
+        value: CellOpt::new(initial),
+        version: Cell::new(0),
+        wakers: Cell::new(Vec::new()),
+        closed: Cell::new(false),
+    });
+    (
+        Sender {
+            shared: Rc::clone(&shared),
+        },
+        Receiver { shared, seen: 0 },
+    )
+}
+
+impl<T> Sender<T> {
+    /// Replaces the value and notifies every receiver. Fails with
+    /// `Error::Borrowed` if called while a receiver is reading the value.
+    pub fn send(&self, value: T) -> Result<(), InsertErr<T>> {
+        self.shared.value.overwrite(value)?;
+        self.shared.version.set(self.shared.version.get() + 1);
+        self.shared.wake_all();
+        Ok(())
+    }
+
+    /// Creates a receiver that has seen the current value.
+    pub fn subscribe(&self) -> Receiver<T> {
+        Receiver {
+            shared: Rc::clone(&self.shared),
+            seen: self.shared.version.get(),
+        }
+    }
+}
+
+impl<T> Drop for Sender<T> {
+    fn drop(&mut self) {
+        self.shared.closed.set(true);
+        self.shared.wake_all();
+    }
+}
+
+impl<T> fmt::Debug for Sender<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Sender")
+            .field("version", &self.shared.version.get())
+            .finish()
+    }
+}
+
+impl<T> Clone for Receiver<T> {
+    fn clone(&self) -> Self {
+        Receiver {
+            shared: Rc::clone(&self.shared),
+            seen: self.seen,
+        }
+    }
+}
+
+impl<T> fmt::Debug for Receiver<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Receiver")
+            .field("seen", &self.seen)
+            .field("version", &self.shared.version.get())
+            .finish()
+    }
+}
+
+impl<T> Receiver<T> {
+    /// Returns `true` if a value has been sent since this receiver last saw
+    /// one. Fails once the sender is gone and nothing new is left to see.
+    pub fn has_changed(&self) -> Result<bool, Closed> {
+        let changed = self.shared.version.get() != self.seen;
+        if !changed && self.shared.closed.get() {
+            Err(Closed)
+        } else {
+            Ok(changed)
+        }
+    }
+
+    /// Waits for a value this receiver has not seen yet and marks it as seen.
+    pub fn changed(&mut self) -> Changed<'_, T> {
+        Changed { receiver: self }
+    }
+
+    fn poll_changed(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Closed>> {
+        match self.has_changed() {
+            Ok(true) => {
+                self.seen = self.shared.version.get();
+                Poll::Ready(Ok(()))
+            }
+            Ok(false) => {
+                self.shared.register(cx.waker());
+                Poll::Pending
+            }
+            Err(closed) => Poll::Ready(Err(closed)),
+        }
+    }
+
+    /// Runs `f` on the current value and marks it as seen.
+    pub fn apply_then_restore<U, F: FnOnce(&T) -> U>(&mut self, f: F) -> Result<U, Error> {
+        self.seen = self.shared.version.get();
+        self.shared.value.apply_then_restore_once(f)
+    }
+
+    /// Clones the current value and marks it as seen.
+    pub fn borrow_and_clone(&mut self) -> Result<T, Error>
+    where
+        T: Clone,
+    {
+        self.apply_then_restore(T::clone)
+    }
+}
+
+/// The future returned by `Receiver::changed`.
+#[must_use = "futures do nothing unless polled"]
+pub struct Changed<'a, T> {
+    receiver: &'a mut Receiver<T>,
+}
+
+impl<T> Future for Changed<'_, T> {
+    type Output = Result<(), Closed>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        self.get_mut().receiver.poll_changed(cx)
+    }
+}
+
+/// A `Stream` of the values a `Receiver` has not seen yet. The stream ends
+/// when the sender is dropped.
+#[cfg(feature = "futures")]
+pub struct WatchStream<T> {
+    receiver: Receiver<T>,
+}
+
+#[cfg(feature = "futures")]
+impl<T> From<Receiver<T>> for WatchStream<T> {
+    fn from(receiver: Receiver<T>) -> Self {
+        WatchStream { receiver }
+    }
+}
+
+#[cfg(feature = "futures")]
+impl<T> WatchStream<T> {
+    pub fn into_inner(self) -> Receiver<T> {
+        self.receiver
+    }
+}
+
+#[cfg(feature = "futures")]
+impl<T: Clone> futures_core::Stream for WatchStream<T> {
+    type Item = T;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<T>> {
+        let receiver = &mut self.get_mut().receiver;
+        match receiver.poll_changed(cx) {
+            // The value cannot be lent out here: readers on this thread are
+            // not running while this task is being polled.
+            Poll::Ready(Ok(())) => Poll::Ready(receiver.borrow_and_clone().ok()),
+            Poll::Ready(Err(Closed)) => Poll::Ready(None),
+            Poll::Pending => Poll::Pending,
+        }
+    }
+}
@@ -0,0 +1,90 @@
+use cellopt::{slice, CellOpt, CellOptArray, Error};
+
+thread_local! {
+    static HANDLERS: CellOptArray<fn(u8) -> u8, 4> = const { CellOptArray::new() };
+    static LAST: CellOpt<u8> = const { CellOpt::new(0) };
+}
+
+#[test]
+fn const_initialized_thread_locals() {
+    LAST.with(|last| assert_eq!(last.replace(1).ok(), Some(Some(0))));
+    HANDLERS.with(|handlers| {
+        assert_eq!(handlers.insert_anywhere(|x| x + 1).ok(), Some(0));
+        assert_eq!(handlers.insert_anywhere(|x| x * 2).ok(), Some(1));
+        assert_eq!(handlers[1].apply_then_restore(|f| f(5)), Ok(10));
+    });
+}
+
+#[test]
+fn insert_and_take_by_index() {
+    let array = CellOptArray::<i32, 3>::new();
+    array.insert_at(1, 10).unwrap();
+    let err = array.insert_at(1, 11).unwrap_err();
+    assert_eq!((err.insert_try, err.err), (11, Error::Occupied));
+    assert_eq!(array.take_at(1), Ok(10));
+    assert_eq!(array.take_at(1), Err(Error::Empty));
+}
+
+#[test]
+#[should_panic]
+fn index_out_of_bounds_panics() {
+    let _ = CellOptArray::<i32, 2>::new().take_at(2);
+}
+
+#[test]
+fn insert_anywhere_fills_gaps_then_fails() {
+    let array = CellOptArray::<i32, 3>::new();
+    array.insert_at(0, 0).unwrap();
+    assert_eq!(array.first_empty(), Some(1));
+    assert_eq!(array.insert_anywhere(1).ok(), Some(1));
+    assert_eq!(array.insert_anywhere(2).ok(), Some(2));
+    assert_eq!(array.first_empty(), None);
+    let err = array.insert_anywhere(3).unwrap_err();
+    assert_eq!((err.insert_try, err.err), (3, Error::Occupied));
+
+    array.take_at(1).unwrap();
+    assert_eq!(array.insert_anywhere(4).ok(), Some(1));
+}
+
+#[test]
+fn lent_and_poisoned_slots_are_not_empty() {
+    let array = CellOptArray::<i32, 3>::new();
+    let _ = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+        array.insert_at(0, 0).unwrap();
+        array[0].update(|_| -> (i32, ()) { panic!() })
+    }));
+    assert!(array[0].is_poisoned());
+    array.insert_at(1, 1).unwrap();
+    array[1]
+        .apply_then_restore(|_| {
+            assert_eq!(array.first_empty(), Some(2));
+            assert_eq!(array.occupied_count(), 1);
+            assert_eq!(array.occupied_indices().collect::<Vec<_>>(), [1]);
+        })
+        .unwrap();
+}
+
+#[test]
+fn counts_and_iterates_occupied() {
+    let array = CellOptArray::<&str, 5>::default();
+    assert_eq!(array.occupied_count(), 0);
+    assert_eq!(array.occupied_indices().next(), None);
+    array.insert_at(3, "d").unwrap();
+    array.insert_at(0, "a").unwrap();
+    assert_eq!(array.occupied_count(), 2);
+    assert_eq!(array.occupied_indices().collect::<Vec<_>>(), [0, 3]);
+}
+
+#[test]
+fn derefs_to_slice() {
+    let array = CellOptArray::from([CellOpt::new(1), CellOpt::empty(), CellOpt::new(3)]);
+    assert_eq!(array.len(), 3);
+    slice::rotate_left(&array, 1).unwrap();
+    assert_eq!(array.occupied_indices().collect::<Vec<_>>(), [1, 2]);
+    assert_eq!(
+        format!("{array:?}"),
+        "CellOptArray([CellOpt(None), CellOpt(Some(3)), CellOpt(Some(1))])"
+    );
+    let [a, _, _] = array.into_inner();
+    assert!(!a.is_occupied());
+}
@@ -0,0 +1,111 @@
+#![cfg(feature = "std")]
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::thread;
+
+use cellopt::{AtomicCellOpt, Error};
+
+static DROPS: AtomicUsize = AtomicUsize::new(0);
+
+struct Counted;
+
+impl Drop for Counted {
+    fn drop(&mut self) {
+        DROPS.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+#[test]
+fn single_thread_api() {
+    let cell = AtomicCellOpt::default();
+    assert!(!cell.is_occupied());
+    assert!(matches!(cell.take(), Err(Error::Empty)));
+    assert!(cell.insert(Box::new(1)).is_ok());
+    assert!(cell.is_occupied());
+    let err = cell.insert(Box::new(2)).unwrap_err();
+    assert!(matches!(err.err, Error::Occupied));
+    assert_eq!(*err.insert_try, 2);
+    assert_eq!(cell.swap(Some(Box::new(3))).as_deref(), Some(&1));
+    cell.overwrite(Box::new(4));
+    assert_eq!(*cell.force_take(), 4);
+    assert_eq!(cell.swap(None), None);
+    assert_eq!(
+        AtomicCellOpt::new(Box::new(5)).into_inner().as_deref(),
+        Some(&5)
+    );
+}
+
+#[test]
+fn drops_contents() {
+    let before = DROPS.load(Ordering::Relaxed);
+    let cell = AtomicCellOpt::new(Box::new(Counted));
+    cell.overwrite(Box::new(Counted));
+    assert!(cell.insert(Box::new(Counted)).is_err());
+    drop(cell);
+    drop(AtomicCellOpt::<Counted>::default());
+    assert_eq!(DROPS.load(Ordering::Relaxed) - before, 3);
+}
+
+#[test]
+fn stress_handoff() {
+    const THREADS: usize = 4;
+    const PER_THREAD: usize = 10_000;
+
+    let cell = AtomicCellOpt::default();
+    let received = AtomicUsize::new(0);
+    let sum = AtomicUsize::new(0);
+    thread::scope(|s| {
+        for t in 0..THREADS {
+            let cell = &cell;
+            s.spawn(move || {
+                for i in 0..PER_THREAD {
+                    let mut value = Box::new(t * PER_THREAD + i);
+                    while let Err(err) = cell.insert(value) {
+                        value = err.insert_try;
+                        thread::yield_now();
+                    }
+                }
+            });
+        }
+        for _ in 0..THREADS {
+            s.spawn(|| {
+                while received.load(Ordering::Relaxed) < THREADS * PER_THREAD {
+                    match cell.take() {
+                        Ok(value) => {
+                            sum.fetch_add(*value, Ordering::Relaxed);
+                            received.fetch_add(1, Ordering::Relaxed);
+                        }
+                        Err(_) => thread::yield_now(),
+                    }
+                }
+            });
+        }
+    });
+    let n = THREADS * PER_THREAD;
+    assert_eq!(sum.load(Ordering::Relaxed), n * (n - 1) / 2);
+    assert!(!cell.is_occupied());
+}
+
+#[test]
+fn stress_swap_loses_nothing() {
+    const THREADS: usize = 4;
+    const PER_THREAD: usize = 10_000;
+
+    let cell = AtomicCellOpt::default();
+    let sum = AtomicUsize::new(0);
+    thread::scope(|s| {
+        for t in 0..THREADS {
+            let (cell, sum) = (&cell, &sum);
+            s.spawn(move || {
+                for i in 0..PER_THREAD {
+                    if let Some(old) = cell.swap(Some(Box::new(t * PER_THREAD + i))) {
+                        sum.fetch_add(*old, Ordering::Relaxed);
+                    }
+                }
+            });
+        }
+    });
+    let last = cell.take().map_or(0, |value| *value);
+    let n = THREADS * PER_THREAD;
+    assert_eq!(sum.load(Ordering::Relaxed) + last, n * (n - 1) / 2);
+}
@@ -0,0 +1,144 @@
+#![cfg(feature = "alloc")]
+
+use std::panic::{catch_unwind, AssertUnwindSafe};
+
+use cellopt::{CellOptBox, Error};
+
+struct Big([u64; 512]);
+
+fn address(cell: &CellOptBox<Big>) -> *const Big {
+    cell.apply_then_restore(|big| big as *const Big).unwrap()
+}
+
+fn first(cell: &CellOptBox<Big>) -> u64 {
+    cell.apply_then_restore(|big| big.0[0]).unwrap()
+}
+
+#[test]
+fn allocation_is_reused_across_take_and_insert() {
+    let cell = CellOptBox::new(Big([1; 512]));
+    let before = address(&cell);
+    let big = cell.take().unwrap();
+    assert_eq!(big.0[0], 1);
+    assert!(cell.insert(Big([2; 512])).is_ok());
+    assert_eq!(address(&cell), before);
+    assert_eq!(first(&cell), 2);
+}
+
+#[test]
+fn overwrite_and_update_keep_the_box() {
+    let cell = CellOptBox::new(Big([1; 512]));
+    let before = address(&cell);
+    assert!(cell.overwrite(Big([2; 512])).is_ok());
+    assert_eq!(address(&cell), before);
+    cell.apply_and_update(|mut big| {
+        big.0[0] += 1;
+        big
+    })
+    .unwrap();
+    assert_eq!(address(&cell), before);
+    assert_eq!(first(&cell), 3);
+    assert_eq!(cell.replace(Big([4; 512])).unwrap().unwrap().0[0], 3);
+    assert_eq!(address(&cell), before);
+}
+
+#[test]
+fn boxed_take_and_overwrite() {
+    let cell = CellOptBox::new(5);
+    let boxed = cell.take_boxed().unwrap();
+    assert_eq!(*boxed, 5);
+    assert_eq!(cell.take(), Err(Error::Empty));
+    cell.overwrite_boxed(Box::new(6)).unwrap();
+    assert_eq!(cell.force_take(), 6);
+}
+
+#[test]
+fn mirrors_cellopt_api() {
+    let cell = CellOptBox::default();
+    assert_eq!(cell.insert_with(|| 2), Ok(()));
+    assert_eq!(cell.insert(3).unwrap_err().error(), Error::Occupied);
+    assert_eq!(cell.get_or_insert_with(|| unreachable!(), |x| x + 1), Ok(3));
+    assert_eq!(cell.update(|x| (x * 10, x)), Ok(2));
+    assert_eq!(cell.map_in_place(|x| *x += 1), Ok(()));
+    assert!(cell.is_some_and(|x| *x == 21));
+    assert_eq!(cell.take_if(|x| *x > 100), Ok(None));
+    cell.filter_in_place(|x| *x > 100).unwrap();
+    assert!(!cell.is_occupied());
+    assert_eq!(cell.xor_insert(1).unwrap(), None);
+    assert_eq!(cell.xor_insert(2).unwrap(), Some((1, 2)));
+    assert_eq!(format!("{:?}", CellOptBox::new(1)), "CellOptBox(Some(1))");
+    let other = CellOptBox::new("x");
+    cell.insert(3).unwrap();
+    assert_eq!(cell.zip_with(&other, |n, s| s.repeat(*n)), Ok("xxx".into()));
+    let guard = cell.lend().unwrap();
+    assert_eq!(**guard, 3);
+    assert_eq!(cell.take(), Err(Error::Borrowed));
+    drop(guard);
+    assert_eq!(cell.into_inner(), Some(3));
+}
+
+#[test]
+fn update_panic_poisons_and_frees() {
+    let cell = CellOptBox::new(String::from("state"));
+    let result = catch_unwind(AssertUnwindSafe(|| {
+        let _ = cell.update(|_| -> (String, ()) { panic!("boom") });
+    }));
+    assert!(result.is_err());
+    assert!(cell.is_poisoned());
+    assert!(cell.overwrite(String::from("fresh")).is_ok());
+    assert_eq!(cell.force_take(), "fresh");
+}
+
+#[test]
+fn moves_and_conversions() {
+    let (a, b) = (CellOptBox::new(1), CellOptBox::empty());
+    let before = a.apply_then_restore(|x| x as *const i32).unwrap();
+    a.move_to(&b).unwrap();
+    assert_eq!(b.apply_then_restore(|x| x as *const i32), Ok(before));
+    assert_eq!(a.take_from(&b), Ok(()));
+    assert_eq!(a.move_to(&a), Err(Error::Occupied));
+    b.insert(2).unwrap();
+    a.swap(&b).unwrap();
+    assert_eq!((a.clone_inner(), b.clone_inner()), (Ok(2), Ok(1)));
+
+    let mut cell = CellOptBox::from(Some(3));
+    *cell.get_mut().unwrap() += 1;
+    assert_eq!(cell.as_option_mut().take().map(|boxed| *boxed), Some(4));
+    assert_eq!(cell.get_mut(), None);
+    *cell.as_option_mut() = Some(Box::new(5));
+    assert_eq!(Option::from(cell), Some(5));
+    assert_eq!(CellOptBox::from(6).into_inner(), Some(6));
+    assert_eq!(CellOptBox::<i32>::from(None).into_inner(), None);
+}
+
+#[test]
+fn compares_and_hashes_like_cellopt() {
+    use std::collections::hash_map::DefaultHasher;
+    use std::hash::{Hash, Hasher};
+
+    use cellopt::CellOpt;
+
+    fn hash<T: Hash>(value: &T) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        value.hash(&mut hasher);
+        hasher.finish()
+    }
+
+    let values = [None, Some(1), Some(2)];
+    for a in values {
+        for b in values {
+            let (boxed_a, boxed_b) = (CellOptBox::<i32>::from(a), CellOptBox::<i32>::from(b));
+            let (cell_a, cell_b) = (CellOpt::<i32>::from(a), CellOpt::<i32>::from(b));
+            assert_eq!(boxed_a == boxed_b, cell_a == cell_b);
+            assert_eq!(boxed_a.cmp(&boxed_b), cell_a.cmp(&cell_b));
+            assert_eq!(hash(&boxed_a), hash(&cell_a));
+        }
+    }
+
+    let cell = CellOptBox::new(1);
+    let result = catch_unwind(AssertUnwindSafe(|| {
+        cell.apply_then_restore(|_| cell == CellOptBox::new(1))
+    }));
+    assert!(result.is_err());
+    assert_eq!(cell.clone_inner(), Ok(1));
+}
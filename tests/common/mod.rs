@@ -0,0 +1,86 @@
+//! A minimal single-threaded executor for driving the async tests.
+
+use std::cell::RefCell;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::task::{Context, Poll, Wake, Waker};
+
+#[derive(Default)]
+pub struct Flag {
+    woken: AtomicBool,
+    wakes: AtomicUsize,
+}
+
+impl Flag {
+    pub fn wakes(&self) -> usize {
+        self.wakes.load(Ordering::Relaxed)
+    }
+}
+
+impl Wake for Flag {
+    fn wake(self: Arc<Self>) {
+        self.wake_by_ref();
+    }
+
+    fn wake_by_ref(self: &Arc<Self>) {
+        self.woken.store(true, Ordering::Relaxed);
+        self.wakes.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+struct Task {
+    future: Pin<Box<dyn Future<Output = ()>>>,
+    flag: Arc<Flag>,
+}
+
+#[derive(Default)]
+pub struct LocalExecutor {
+    tasks: RefCell<Vec<Task>>,
+}
+
+impl LocalExecutor {
+    pub fn spawn(&self, future: impl Future<Output = ()> + 'static) -> Arc<Flag> {
+        let flag = Arc::new(Flag::default());
+        flag.woken.store(true, Ordering::Relaxed);
+        self.tasks.borrow_mut().push(Task {
+            future: Box::pin(future),
+            flag: Arc::clone(&flag),
+        });
+        flag
+    }
+
+    /// Polls woken tasks until none are left to poll, and returns the number
+    /// of tasks that have not finished.
+    pub fn run_until_stalled(&self) -> usize {
+        loop {
+            let mut tasks = self.tasks.take();
+            let mut progressed = false;
+            tasks.retain_mut(|task| {
+                if !task.flag.woken.swap(false, Ordering::Relaxed) {
+                    return true;
+                }
+                progressed = true;
+                let waker = Waker::from(Arc::clone(&task.flag));
+                let mut cx = Context::from_waker(&waker);
+                task.future.as_mut().poll(&mut cx).is_pending()
+            });
+            // Tasks spawned while polling were pushed onto the emptied list.
+            tasks.append(&mut self.tasks.borrow_mut());
+            *self.tasks.borrow_mut() = tasks;
+            if !progressed {
+                return self.tasks.borrow().len();
+            }
+        }
+    }
+}
+
+/// Polls `future` once with a fresh waker.
+#[allow(dead_code)]
+pub fn poll_once<F: Future + Unpin>(future: &mut F) -> (Poll<F::Output>, Arc<Flag>) {
+    let flag = Arc::new(Flag::default());
+    let waker = Waker::from(Arc::clone(&flag));
+    let poll = Pin::new(future).poll(&mut Context::from_waker(&waker));
+    (poll, flag)
+}
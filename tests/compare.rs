@@ -0,0 +1,90 @@
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashSet;
+use std::hash::{Hash, Hasher};
+use std::panic::{catch_unwind, AssertUnwindSafe};
+
+use cellopt::CellOpt;
+
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+struct Entry {
+    id: u32,
+    value: CellOpt<String>,
+}
+
+fn cell(value: Option<i32>) -> CellOpt<i32> {
+    value.map_or_else(CellOpt::default, CellOpt::new)
+}
+
+fn hash<T: Hash>(value: &T) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    value.hash(&mut hasher);
+    hasher.finish()
+}
+
+#[test]
+fn matches_option() {
+    let options = [None, Some(1), Some(2)];
+    for a in options {
+        for b in options {
+            let (ca, cb) = (cell(a), cell(b));
+            assert_eq!(ca == cb, a == b);
+            assert_eq!(ca.partial_cmp(&cb), a.partial_cmp(&b));
+            assert_eq!(ca.cmp(&cb), a.cmp(&b));
+        }
+        assert_eq!(hash(&cell(a)), hash(&a));
+    }
+}
+
+#[test]
+fn comparison_leaves_values_in_place() {
+    let a = CellOpt::new(String::from("a"));
+    let b = CellOpt::new(String::from("b"));
+    assert!(a < b);
+    assert_eq!(a.clone_inner().unwrap(), "a");
+    assert_eq!(b.clone_inner().unwrap(), "b");
+}
+
+#[test]
+#[allow(clippy::mutable_key_type)]
+fn hash_set_of_structs() {
+    let mut set = HashSet::new();
+    let entry = Entry {
+        id: 1,
+        value: CellOpt::new(String::from("x")),
+    };
+    assert!(set.insert(entry.clone()));
+    assert!(!set.insert(entry.clone()));
+    assert!(set.insert(Entry {
+        id: 1,
+        value: CellOpt::default(),
+    }));
+    assert!(set.contains(&entry));
+    assert_eq!(set.len(), 2);
+}
+
+#[test]
+#[allow(clippy::eq_op)]
+fn self_comparison() {
+    let a = CellOpt::new(1);
+    assert!(a == a);
+    assert_eq!(a.cmp(&a), std::cmp::Ordering::Equal);
+
+    // As with `Option<f64>`, NaN is not equal to itself.
+    let nan = CellOpt::new(f64::NAN);
+    assert!(nan != nan);
+    assert_eq!(nan.partial_cmp(&nan), None);
+    assert!(nan.is_occupied());
+}
+
+#[test]
+fn comparing_a_lent_cell_panics() {
+    let a = CellOpt::new(1);
+    let b = CellOpt::new(1);
+    a.apply_then_restore(|_| {
+        let result = catch_unwind(AssertUnwindSafe(|| a == b));
+        assert!(result.is_err());
+    })
+    .unwrap();
+    // The lend ended normally and the values are intact.
+    assert!(a == b);
+}
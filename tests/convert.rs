@@ -0,0 +1,85 @@
+use std::panic::{catch_unwind, AssertUnwindSafe};
+
+use cellopt::{CellOpt, Error};
+
+#[test]
+fn from_value_and_option() {
+    let cell: CellOpt<i32> = 1.into();
+    assert_eq!(cell.into_inner(), Some(1));
+    let cell: CellOpt<i32> = Some(2).into();
+    assert_eq!(Option::from(cell), Some(2));
+    let cell: CellOpt<i32> = None.into();
+    assert!(!cell.is_occupied());
+    assert_eq!(Option::<i32>::from(cell), None);
+}
+
+#[test]
+fn get_mut() {
+    let mut cell = CellOpt::new(vec![1]);
+    cell.get_mut().unwrap().push(2);
+    assert_eq!(cell.clone_inner().unwrap(), vec![1, 2]);
+    cell.take().unwrap();
+    assert_eq!(cell.get_mut(), None);
+}
+
+#[test]
+fn as_option_mut() {
+    let mut cell = CellOpt::default();
+    *cell.as_option_mut() = Some(3);
+    assert_eq!(cell.as_option_mut().take(), Some(3));
+    assert_eq!(cell.take(), Err(Error::Empty));
+}
+
+#[test]
+fn as_option_mut_clears_poison() {
+    let mut cell = CellOpt::new(1);
+    let result = catch_unwind(AssertUnwindSafe(|| {
+        let _ = cell.apply_and_update(|_| panic!("boom"));
+    }));
+    assert!(result.is_err());
+    assert!(cell.is_poisoned());
+    assert_eq!(cell.get_mut(), None);
+    *cell.as_option_mut() = Some(4);
+    assert!(!cell.is_poisoned());
+    assert_eq!(cell.into_inner(), Some(4));
+}
+
+#[test]
+fn scoped_writes_back() {
+    let mut option = Some(1);
+    let seen = CellOpt::scoped(&mut option, |cell| {
+        let seen = cell.take().unwrap();
+        cell.insert(seen + 1).unwrap();
+        seen
+    });
+    assert_eq!((seen, option), (1, Some(2)));
+
+    let mut option = None;
+    CellOpt::scoped(&mut option, |cell| cell.insert("new").unwrap());
+    assert_eq!(option, Some("new"));
+}
+
+#[test]
+fn scoped_writes_back_on_panic() {
+    let mut option = Some(vec![1]);
+    let result = catch_unwind(AssertUnwindSafe(|| {
+        CellOpt::scoped(&mut option, |cell| {
+            cell.map_in_place(|v| v.push(2)).unwrap();
+            panic!("boom");
+        })
+    }));
+    assert!(result.is_err());
+    assert_eq!(option, Some(vec![1, 2]));
+
+    // A forgotten guard leaves the cell lent, but the value still comes back.
+    CellOpt::scoped(&mut option, |cell| std::mem::forget(cell.lend().unwrap()));
+    assert_eq!(option, Some(vec![1, 2]));
+
+    let result = catch_unwind(AssertUnwindSafe(|| {
+        CellOpt::scoped(&mut option, |cell| {
+            let _ = cell.apply_and_update(|_| panic!("boom"));
+        })
+    }));
+    assert!(result.is_err());
+    assert_eq!(option, None);
+}
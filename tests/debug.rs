@@ -0,0 +1,70 @@
+use cellopt::CellOpt;
+
+#[derive(Debug)]
+#[allow(dead_code)]
+struct Inner {
+    id: u32,
+    name: CellOpt<String>,
+}
+
+#[derive(Debug)]
+#[allow(dead_code)]
+struct Outer {
+    inner: CellOpt<Inner>,
+    empty: CellOpt<u32>,
+}
+
+fn outer() -> Outer {
+    Outer {
+        inner: CellOpt::new(Inner {
+            id: 7,
+            name: CellOpt::new(String::from("seven")),
+        }),
+        empty: CellOpt::default(),
+    }
+}
+
+#[test]
+fn compact() {
+    assert_eq!(
+        format!("{:?}", outer()),
+        r#"Outer { inner: CellOpt(Some(Inner { id: 7, name: CellOpt(Some("seven")) })), empty: CellOpt(None) }"#
+    );
+}
+
+#[test]
+fn pretty() {
+    let expected = r#"Outer {
+    inner: CellOpt(
+        Some(
+            Inner {
+                id: 7,
+                name: CellOpt(
+                    Some(
+                        "seven",
+                    ),
+                ),
+            },
+        ),
+    ),
+    empty: CellOpt(
+        None,
+    ),
+}"#;
+    assert_eq!(format!("{:#?}", outer()), expected);
+}
+
+#[test]
+fn reentrant_marker() {
+    let outer = outer();
+    outer
+        .inner
+        .apply_then_restore(|_| {
+            assert_eq!(
+                format!("{:?}", outer),
+                "Outer { inner: CellOpt(<in use>), empty: CellOpt(None) }"
+            );
+            assert_eq!(format!("{:#?}", outer.inner), "CellOpt(\n    <in use>,\n)");
+        })
+        .unwrap();
+}
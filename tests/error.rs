@@ -0,0 +1,52 @@
+use cellopt::{CellOpt, Error};
+
+#[test]
+fn display() {
+    assert_eq!(Error::Empty.to_string(), "cell is empty");
+    assert_eq!(Error::Occupied.to_string(), "cell is occupied");
+    let err = CellOpt::new(1).insert(2).unwrap_err();
+    assert_eq!(err.to_string(), "cannot insert value: cell is occupied");
+}
+
+#[test]
+fn insert_err_debug_does_not_need_debug_value() {
+    struct Opaque;
+    let err = CellOpt::new(Opaque).insert(Opaque).unwrap_err();
+    assert_eq!(format!("{:?}", err), "InsertErr { err: Occupied, .. }");
+}
+
+#[test]
+fn insert_err_accessors() {
+    let err = CellOpt::new(1).insert(2).unwrap_err();
+    assert_eq!(err.error(), Error::Occupied);
+    assert_eq!(err.into_inner(), 2);
+}
+
+fn move_value(from: &CellOpt<u32>, to: &CellOpt<u32>) -> Result<(), Error> {
+    to.insert(from.take()?)?;
+    Ok(())
+}
+
+#[test]
+fn question_mark_into_error() {
+    let from = CellOpt::new(1);
+    let to = CellOpt::default();
+    assert_eq!(move_value(&from, &to), Ok(()));
+    assert_eq!(move_value(&from, &to), Err(Error::Empty));
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn boxed_std_errors() {
+    fn insert_twice() -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        let cell = CellOpt::new(1);
+        cell.insert(2)?;
+        Ok(())
+    }
+    let err = insert_twice().unwrap_err();
+    let err = err.downcast::<cellopt::InsertErr<i32>>().unwrap();
+    assert_eq!(err.into_inner(), 2);
+
+    let err: Box<dyn std::error::Error> = Box::new(Error::Borrowed);
+    assert_eq!(err.to_string(), "cell value is lent out");
+}
@@ -0,0 +1,59 @@
+use std::panic::{catch_unwind, AssertUnwindSafe};
+
+use cellopt::{CellOpt, Error};
+
+#[test]
+fn restores_on_drop() {
+    let cell = CellOpt::new(vec![1]);
+    {
+        let mut guard = cell.lend().unwrap();
+        guard.push(2);
+        assert_eq!(guard.len(), 2);
+    }
+    assert_eq!(cell.force_take(), vec![1, 2]);
+}
+
+#[test]
+fn reentrant_access_while_lent() {
+    let cell = CellOpt::new(1);
+    let guard = cell.lend().unwrap();
+    assert!(cell.is_occupied());
+    assert_eq!(cell.take(), Err(Error::Borrowed));
+    assert_eq!(cell.lend().unwrap_err(), Error::Borrowed);
+    assert_eq!(cell.insert(2).unwrap_err().error(), Error::Borrowed);
+    drop(guard);
+    assert_eq!(cell.force_take(), 1);
+    assert_eq!(cell.lend().unwrap_err(), Error::Empty);
+}
+
+#[test]
+fn keep_and_consume_leave_cell_empty() {
+    let cell = CellOpt::new(String::from("a"));
+    let mut guard = cell.lend().unwrap();
+    guard.push('b');
+    assert_eq!(guard.keep(), "ab");
+    assert_eq!(cell.take(), Err(Error::Empty));
+
+    cell.insert(String::from("c")).unwrap();
+    cell.lend().unwrap().consume();
+    assert_eq!(cell.take(), Err(Error::Empty));
+    assert!(cell.insert(String::from("d")).is_ok());
+}
+
+#[test]
+fn restores_on_panic() {
+    let cell = CellOpt::new(1);
+    let result = catch_unwind(AssertUnwindSafe(|| {
+        let mut guard = cell.lend().unwrap();
+        *guard += 1;
+        panic!("boom");
+    }));
+    assert!(result.is_err());
+    assert_eq!(cell.force_take(), 2);
+}
+
+#[test]
+fn debug() {
+    let cell = CellOpt::new(1);
+    assert_eq!(format!("{:?}", cell.lend().unwrap()), "TakeGuard(1)");
+}
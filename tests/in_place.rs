@@ -0,0 +1,71 @@
+// These tests exercise the paths that read the value in place through
+// `Cell::as_ptr`; run them under Miri with `cargo +nightly miri test`.
+
+use std::panic::{catch_unwind, AssertUnwindSafe};
+
+use cellopt::{CellOpt, Error};
+
+struct Big([u8; 4096]);
+
+fn address<T>(value: &T) -> *const T {
+    value
+}
+
+#[test]
+fn value_is_not_moved() {
+    let cell = CellOpt::new(Big([7; 4096]));
+    let first = cell.apply_then_restore(address).unwrap();
+    assert!(cell.is_occupied());
+    let second = cell.apply_then_restore(address).unwrap();
+    let third = cell.map_in_place(|big| address(&*big)).unwrap();
+    let guard = cell.lend().unwrap();
+    assert_eq!(first, second);
+    assert_eq!(first, third);
+    assert_eq!(first, address(&*guard));
+    assert_eq!(guard.0[4095], 7);
+}
+
+#[test]
+fn reentrant_access_does_not_touch_lent_value() {
+    let cell = CellOpt::new(vec![1, 2, 3]);
+    cell.apply_then_restore(|v| {
+        assert!(cell.is_occupied());
+        assert!(!cell.is_poisoned());
+        assert_eq!(cell.take(), Err(Error::Borrowed));
+        assert_eq!(cell.clone_inner(), Err(Error::Borrowed));
+        assert_eq!(cell.insert(vec![]).unwrap_err().error(), Error::Borrowed);
+        assert_eq!(cell.overwrite(vec![]).unwrap_err().error(), Error::Borrowed);
+        assert_eq!(format!("{:?}", cell), "CellOpt(<in use>)");
+        assert_eq!(v.len(), 3);
+    })
+    .unwrap();
+    assert_eq!(cell.force_take(), vec![1, 2, 3]);
+}
+
+#[test]
+fn guard_mutation_with_reentrant_reads() {
+    let cell = CellOpt::new(String::from("a"));
+    let mut guard = cell.lend().unwrap();
+    guard.push('b');
+    assert!(cell.is_occupied());
+    assert_eq!(cell.take(), Err(Error::Borrowed));
+    guard.push('c');
+    drop(guard);
+    assert_eq!(cell.clone_inner().unwrap(), "abc");
+    assert_eq!(cell.lend().unwrap().keep(), "abc");
+    assert!(!cell.is_occupied());
+}
+
+#[test]
+fn panic_during_in_place_mutation_keeps_value() {
+    let cell = CellOpt::new(vec![1]);
+    let result = catch_unwind(AssertUnwindSafe(|| {
+        let _ = cell.map_in_place(|v| {
+            v.push(2);
+            panic!("boom");
+        });
+    }));
+    assert!(result.is_err());
+    assert!(!cell.is_poisoned());
+    assert_eq!(cell.force_take(), vec![1, 2]);
+}
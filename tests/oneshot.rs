@@ -0,0 +1,96 @@
+#![cfg(feature = "alloc")]
+
+mod common;
+
+use std::rc::Rc;
+use std::task::Poll;
+
+use cellopt::oneshot::{channel, Canceled};
+use cellopt::CellOpt;
+use common::{poll_once, LocalExecutor};
+
+#[test]
+fn value_sent_later_wakes_receiver() {
+    let executor = LocalExecutor::default();
+    let (sender, receiver) = channel();
+    let result = Rc::new(CellOpt::default());
+    let out = Rc::clone(&result);
+    let flag = executor.spawn(async move {
+        out.insert(receiver.await).unwrap();
+    });
+    assert_eq!(executor.run_until_stalled(), 1);
+    assert!(!result.is_occupied());
+    sender.send(String::from("hello")).unwrap();
+    assert_eq!(flag.wakes(), 1);
+    assert_eq!(executor.run_until_stalled(), 0);
+    assert_eq!(result.force_take(), Ok(String::from("hello")));
+}
+
+#[test]
+fn value_sent_before_first_poll() {
+    let (sender, mut receiver) = channel();
+    sender.send(1).unwrap();
+    let (poll, _) = poll_once(&mut receiver);
+    assert_eq!(poll, Poll::Ready(Ok(1)));
+}
+
+#[test]
+fn dropping_sender_cancels() {
+    let (sender, mut receiver) = channel::<u8>();
+    let (poll, flag) = poll_once(&mut receiver);
+    assert_eq!(poll, Poll::Pending);
+    drop(sender);
+    assert_eq!(flag.wakes(), 1);
+    let (poll, _) = poll_once(&mut receiver);
+    assert_eq!(poll, Poll::Ready(Err(Canceled)));
+}
+
+#[test]
+fn dropping_receiver_fails_send() {
+    let (sender, receiver) = channel();
+    assert!(!sender.is_canceled());
+    drop(receiver);
+    assert!(sender.is_canceled());
+    assert_eq!(sender.send(5), Err(5));
+}
+
+#[test]
+fn try_recv() {
+    let (sender, mut receiver) = channel();
+    assert_eq!(receiver.try_recv(), Ok(None));
+    sender.send(2).unwrap();
+    assert_eq!(receiver.try_recv(), Ok(Some(2)));
+    assert_eq!(receiver.try_recv(), Err(Canceled));
+}
+
+#[test]
+fn repolling_with_same_waker_does_not_wake_twice() {
+    let executor = LocalExecutor::default();
+    let (sender, receiver) = channel();
+    let flag = executor.spawn(async move {
+        let _ = receiver.await;
+    });
+    executor.run_until_stalled();
+    sender.send(()).unwrap();
+    assert_eq!(executor.run_until_stalled(), 0);
+    assert_eq!(flag.wakes(), 1);
+}
+
+#[test]
+fn pair_of_tasks() {
+    let executor = LocalExecutor::default();
+    let (to_b, from_a) = channel();
+    let (to_a, from_b) = channel();
+    let result = Rc::new(CellOpt::default());
+    let out = Rc::clone(&result);
+    executor.spawn(async move {
+        to_b.send(20).unwrap();
+        out.insert(from_b.await.unwrap()).unwrap();
+    });
+    executor.spawn(async move {
+        let n = from_a.await.unwrap();
+        to_a.send(n + 1).unwrap();
+    });
+    assert_eq!(executor.run_until_stalled(), 0);
+    assert_eq!(result.force_take(), 21);
+}
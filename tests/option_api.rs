@@ -0,0 +1,100 @@
+use std::panic::{catch_unwind, AssertUnwindSafe};
+
+use cellopt::{CellOpt, Error};
+
+#[test]
+fn replace() {
+    let cell = CellOpt::default();
+    assert_eq!(cell.replace(1).unwrap(), None);
+    assert_eq!(cell.replace(2).unwrap(), Some(1));
+    cell.apply_then_restore(|_| {
+        let err = cell.replace(3).unwrap_err();
+        assert_eq!(err.error(), Error::Borrowed);
+    })
+    .unwrap();
+    assert_eq!(cell.force_take(), 2);
+}
+
+#[test]
+fn take_if() {
+    let cell = CellOpt::new(4);
+    assert_eq!(cell.take_if(|x| x % 2 == 1), Ok(None));
+    assert!(cell.is_occupied());
+    assert_eq!(cell.take_if(|x| x % 2 == 0), Ok(Some(4)));
+    assert_eq!(cell.take_if(|_| true), Err(Error::Empty));
+}
+
+#[test]
+fn is_some_and() {
+    let cell = CellOpt::new(4);
+    assert!(cell.is_some_and(|x| *x == 4));
+    assert!(!cell.is_some_and(|x| *x == 5));
+    cell.take().unwrap();
+    assert!(!cell.is_some_and(|_| true));
+}
+
+#[test]
+fn insert_with_only_calls_f_when_empty() {
+    let cell = CellOpt::new(1);
+    assert_eq!(cell.insert_with(|| unreachable!()), Err(Error::Occupied));
+    cell.take().unwrap();
+    assert_eq!(cell.insert_with(|| 2), Ok(()));
+    assert_eq!(cell.force_take(), 2);
+}
+
+#[test]
+fn get_or_insert_with() {
+    let cell = CellOpt::default();
+    assert_eq!(cell.get_or_insert_with(|| 3, |x| x * 2), Ok(6));
+    assert_eq!(cell.get_or_insert_with(|| unreachable!(), |x| x * 3), Ok(9));
+    assert_eq!(cell.force_take(), 3);
+}
+
+#[test]
+fn map_in_place() {
+    let cell = CellOpt::new(vec![1, 2]);
+    assert_eq!(cell.map_in_place(|v| v.push(3)), Ok(()));
+    assert_eq!(
+        cell.map_in_place(|_| cell.take().unwrap_err()),
+        Ok(Error::Borrowed)
+    );
+    let result = catch_unwind(AssertUnwindSafe(|| {
+        let _ = cell.map_in_place(|v| {
+            v.push(4);
+            panic!("boom")
+        });
+    }));
+    assert!(result.is_err());
+    assert_eq!(cell.force_take(), vec![1, 2, 3, 4]);
+}
+
+#[test]
+fn filter_in_place() {
+    let cell = CellOpt::new(4);
+    cell.filter_in_place(|x| *x > 3).unwrap();
+    assert!(cell.is_occupied());
+    cell.filter_in_place(|x| *x > 4).unwrap();
+    assert!(!cell.is_occupied());
+}
+
+#[test]
+fn zip_with() {
+    let a = CellOpt::new(2);
+    let b = CellOpt::new(String::from("ab"));
+    assert_eq!(
+        a.zip_with(&b, |n, s| s.repeat(*n)),
+        Ok(String::from("abab"))
+    );
+    assert_eq!(a.zip_with(&a, |x, y| x + y), Err(Error::Borrowed));
+    b.take().unwrap();
+    assert_eq!(a.zip_with(&b, |_, _| ()), Err(Error::Empty));
+}
+
+#[test]
+fn xor_insert() {
+    let cell = CellOpt::default();
+    assert_eq!(cell.xor_insert(1).unwrap(), None);
+    assert!(cell.is_occupied());
+    assert_eq!(cell.xor_insert(2).unwrap(), Some((1, 2)));
+    assert!(!cell.is_occupied());
+}
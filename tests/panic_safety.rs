@@ -0,0 +1,77 @@
+use std::panic::{catch_unwind, AssertUnwindSafe};
+
+use cellopt::{CellOpt, Error};
+
+#[test]
+fn apply_then_restore_restores_after_panic() {
+    let cell = CellOpt::new(String::from("state"));
+    let result = catch_unwind(AssertUnwindSafe(|| {
+        let _ = cell.apply_then_restore(|_| panic!("boom"));
+    }));
+    assert!(result.is_err());
+    assert!(cell.is_occupied());
+    assert_eq!(cell.force_take(), "state");
+}
+
+#[test]
+fn apply_then_restore_returns_result() {
+    let cell = CellOpt::new(vec![1, 2, 3]);
+    assert_eq!(cell.apply_then_restore(|v| v.len()).unwrap(), 3);
+    assert_eq!(cell.force_take(), vec![1, 2, 3]);
+}
+
+fn poisoned() -> CellOpt<String> {
+    let cell = CellOpt::new(String::from("state"));
+    let result = catch_unwind(AssertUnwindSafe(|| {
+        let _ = cell.apply_and_update(|_| panic!("boom"));
+    }));
+    assert!(result.is_err());
+    cell
+}
+
+#[test]
+fn apply_and_update_poisons_after_panic() {
+    let cell = poisoned();
+    assert!(cell.is_poisoned());
+    assert!(!cell.is_occupied());
+    assert!(matches!(cell.take(), Err(Error::Poisoned)));
+    assert!(matches!(
+        cell.apply_then_restore(|_| ()),
+        Err(Error::Poisoned)
+    ));
+    assert!(matches!(cell.apply_and_update(|s| s), Err(Error::Poisoned)));
+    let err = cell.insert(String::from("fresh")).unwrap_err();
+    assert!(matches!(err.err, Error::Poisoned));
+    assert_eq!(format!("{:?}", cell), "CellOpt(<poisoned>)");
+    assert!(cell.clone().is_poisoned());
+}
+
+#[test]
+fn clear_poison_empties_cell() {
+    let cell = poisoned();
+    cell.clear_poison();
+    assert!(!cell.is_poisoned());
+    assert!(matches!(cell.take(), Err(Error::Empty)));
+    assert!(cell.insert(String::from("fresh")).is_ok());
+}
+
+#[test]
+fn overwrite_clears_poison() {
+    let cell = poisoned();
+    assert!(cell.overwrite(String::from("fresh")).is_ok());
+    assert!(!cell.is_poisoned());
+    assert_eq!(cell.force_take(), "fresh");
+}
+
+#[test]
+fn poisoned_value_is_gone() {
+    assert_eq!(poisoned().into_inner(), None);
+    assert_eq!(CellOpt::new(1).into_inner(), Some(1));
+}
+
+#[test]
+fn apply_and_update_updates() {
+    let cell = CellOpt::new(1);
+    cell.apply_and_update(|x| x + 1).unwrap();
+    assert_eq!(cell.force_take(), 2);
+}
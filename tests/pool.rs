@@ -0,0 +1,205 @@
+#![cfg(feature = "alloc")]
+
+use cellopt::pool::{Pool, Stats};
+use cellopt::Error;
+
+/// Stands in for an expensive object with no `Default` or `Copy`.
+#[derive(Debug, PartialEq)]
+struct Parser {
+    id: u32,
+    buffer: Vec<u8>,
+}
+
+fn parser(id: u32) -> Parser {
+    Parser {
+        id,
+        buffer: Vec::new(),
+    }
+}
+
+#[test]
+fn checkout_returns_value_on_drop() {
+    let pool = Pool::new(2);
+    pool.insert(parser(1)).unwrap();
+    {
+        let mut guard = pool.checkout().unwrap();
+        guard.buffer.push(7);
+        assert_eq!(pool.idle(), 0);
+        assert!(pool.checkout().is_none());
+    }
+    assert_eq!(pool.idle(), 1);
+    let guard = pool.checkout().unwrap();
+    assert_eq!(
+        *guard,
+        Parser {
+            id: 1,
+            buffer: vec![7]
+        }
+    );
+    assert_eq!(
+        pool.stats(),
+        Stats {
+            hits: 2,
+            misses: 1,
+            outstanding: 1
+        }
+    );
+}
+
+#[test]
+fn checkout_or_create_counts_misses() {
+    let pool = Pool::new(1);
+    let mut created = 0;
+    for _ in 0..3 {
+        let guard = pool
+            .checkout_or_create(|| {
+                created += 1;
+                parser(created)
+            })
+            .unwrap();
+        assert_eq!(guard.id, 1);
+    }
+    assert_eq!(created, 1);
+    assert_eq!(
+        pool.stats(),
+        Stats {
+            hits: 2,
+            misses: 1,
+            outstanding: 0
+        }
+    );
+}
+
+#[test]
+fn max_size_bounds_live_values() {
+    let pool = Pool::new(2);
+    assert_eq!(pool.max_size(), 2);
+    let first = pool.checkout_or_create(|| parser(0)).unwrap();
+    let second = pool.checkout_or_create(|| parser(1)).unwrap();
+    assert_eq!((pool.len(), pool.stats().outstanding), (2, 2));
+    // Everything is checked out and the pool is at its maximum size.
+    assert!(pool.checkout_or_create(|| unreachable!()).is_none());
+    let err = pool.insert(parser(9)).unwrap_err();
+    assert_eq!((err.insert_try.id, err.err), (9, Error::Occupied));
+
+    drop(first);
+    assert_eq!(pool.checkout_or_create(|| unreachable!()).unwrap().id, 0);
+    // Detaching makes room for a new value.
+    assert_eq!(second.detach().id, 1);
+    assert_eq!(pool.len(), 1);
+    assert_eq!(pool.checkout_or_create(|| parser(2)).unwrap().id, 0);
+    pool.insert(parser(3)).unwrap();
+    assert_eq!((pool.len(), pool.idle()), (2, 2));
+
+    let empty = Pool::new(0);
+    assert!(empty.checkout_or_create(|| parser(0)).is_none());
+    assert!(empty.is_empty());
+}
+
+#[test]
+fn panicking_factory_gives_back_its_space() {
+    let pool = Pool::<Parser>::new(1);
+    let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+        pool.checkout_or_create(|| panic!("boom"))
+    }));
+    assert!(result.is_err());
+    assert!(pool.is_empty());
+    assert_eq!(pool.checkout_or_create(|| parser(1)).unwrap().id, 1);
+}
+
+#[test]
+fn detach_keeps_value_out() {
+    let pool = Pool::new(1);
+    pool.insert(parser(1)).unwrap();
+    let value = pool.checkout().unwrap().detach();
+    assert_eq!(value.id, 1);
+    assert_eq!(pool.idle(), 0);
+    assert_eq!(pool.stats().outstanding, 0);
+}
+
+#[test]
+fn guards_nest_through_shared_reference() {
+    let pool = Pool::new(3);
+    let outer = pool.checkout_or_create(|| parser(1)).unwrap();
+    let inner = pool.checkout_or_create(|| parser(2)).unwrap();
+    drop(outer);
+    let again = pool.checkout().unwrap();
+    assert_eq!(again.id, 1);
+    drop(inner);
+    assert_eq!(pool.stats().outstanding, 1);
+}
+
+#[cfg(feature = "std")]
+mod sync {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+    use std::thread;
+
+    use cellopt::pool::{Stats, SyncPool};
+
+    fn assert_sync<T: Sync + Send>() {}
+
+    #[test]
+    fn shared_across_threads() {
+        assert_sync::<SyncPool<Vec<u8>>>();
+        let pool = Arc::new(SyncPool::new(4));
+        let created = Arc::new(AtomicUsize::new(0));
+        let handles: Vec<_> = (0..8)
+            .map(|_| {
+                let (pool, created) = (pool.clone(), created.clone());
+                thread::spawn(move || {
+                    for _ in 0..100 {
+                        // The pool may be at its maximum size with every
+                        // buffer checked out; wait for one to come back.
+                        let mut buffer = loop {
+                            let create = || {
+                                created.fetch_add(1, Ordering::Relaxed);
+                                Vec::new()
+                            };
+                            match pool.checkout_or_create(create) {
+                                Some(buffer) => break buffer,
+                                None => thread::yield_now(),
+                            }
+                        };
+                        buffer.push(1u8);
+                    }
+                })
+            })
+            .collect();
+        for handle in handles {
+            handle.join().unwrap();
+        }
+        let created = created.load(Ordering::Relaxed);
+        assert!(created <= pool.max_size());
+        assert_eq!(pool.stats().outstanding, 0);
+        // Every buffer came back, so together they saw every push.
+        assert_eq!((pool.len(), pool.idle()), (created, created));
+        let pushes: usize = std::iter::from_fn(|| pool.checkout().map(|b| b.detach().len())).sum();
+        assert_eq!(pushes, 800);
+        assert!(pool.is_empty());
+    }
+
+    #[test]
+    fn mirrors_local_pool() {
+        let pool = SyncPool::new(1);
+        pool.insert(1).unwrap();
+        assert!(pool.insert(2).is_err());
+        let guard = pool.checkout().unwrap();
+        assert_eq!(*guard, 1);
+        assert!(pool.checkout().is_none());
+        drop(guard);
+        assert_eq!(pool.checkout().unwrap().detach(), 1);
+        assert_eq!(pool.checkout_or_create(|| 3).map(|g| *g), Some(3));
+        let guard = pool.checkout().unwrap();
+        assert!(pool.checkout_or_create(|| 4).is_none());
+        drop(guard);
+        assert_eq!(
+            pool.stats(),
+            Stats {
+                hits: 3,
+                misses: 3,
+                outstanding: 0
+            }
+        );
+    }
+}
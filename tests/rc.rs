@@ -0,0 +1,94 @@
+#![cfg(feature = "alloc")]
+
+use std::collections::HashMap;
+
+use cellopt::{CellOpt, Error, RcCellOpt, WeakCellOpt};
+
+#[test]
+fn producer_and_consumer_share_a_slot() {
+    let producer = RcCellOpt::default();
+    let consumer = producer.clone();
+    assert!(producer.ptr_eq(&consumer));
+    producer.insert(1).unwrap();
+    assert_eq!(consumer.take(), Ok(1));
+    assert!(!producer.is_occupied());
+}
+
+#[test]
+fn weak_handle_forwards_while_alive() {
+    let strong = RcCellOpt::new(vec![1]);
+    let weak = strong.downgrade();
+    assert!(weak.is_occupied());
+    weak.map_in_place(|v| v.push(2)).unwrap();
+    assert_eq!(weak.clone_inner(), Ok(vec![1, 2]));
+    assert_eq!(weak.update(|v| (v, 3)), Ok(3));
+    assert_eq!(weak.take(), Ok(vec![1, 2]));
+    assert!(weak.insert(vec![3]).is_ok());
+    assert_eq!(strong.force_take(), vec![3]);
+    assert!(weak.upgrade().unwrap().ptr_eq(&strong));
+}
+
+#[test]
+fn weak_handle_reports_dropped() {
+    let strong = RcCellOpt::new(1);
+    let weak = strong.downgrade();
+    drop(strong);
+    assert_eq!(weak.take(), Err(Error::Dropped));
+    assert_eq!(weak.apply_then_restore(|x| *x), Err(Error::Dropped));
+    let err = weak.insert(2).unwrap_err();
+    assert_eq!(err.error(), Error::Dropped);
+    assert_eq!(err.into_inner(), 2);
+    assert_eq!(weak.overwrite(3).unwrap_err().error(), Error::Dropped);
+    assert!(!weak.is_occupied());
+    assert!(!weak.is_some_and(|_| true));
+    assert!(weak.upgrade().is_none());
+    assert_eq!(format!("{:?}", weak), "WeakCellOpt(<dropped>)");
+    assert_eq!(WeakCellOpt::<u8>::default().take(), Err(Error::Dropped));
+}
+
+#[test]
+fn weak_handle_moves_values() {
+    let strong = RcCellOpt::new(1);
+    let weak = strong.downgrade();
+    let other = CellOpt::default();
+    weak.move_to(&other).unwrap();
+    assert!(!strong.is_occupied());
+    weak.take_from(&other).unwrap();
+    other.insert(2).unwrap();
+    weak.swap(&other).unwrap();
+    assert_eq!((strong.clone_inner(), other.clone_inner()), (Ok(2), Ok(1)));
+
+    drop(strong);
+    assert_eq!(weak.swap(&other), Err(Error::Dropped));
+    assert_eq!(weak.move_to(&other), Err(Error::Dropped));
+    assert_eq!(weak.take_from(&other), Err(Error::Dropped));
+    assert_eq!(other.clone_inner(), Ok(1));
+}
+
+#[test]
+fn try_update_through_weak_converts_dropped() {
+    let weak = RcCellOpt::new(1).downgrade();
+    let result: Result<(), Error> = weak.try_update(|x| Ok(x + 1));
+    assert_eq!(result, Err(Error::Dropped));
+}
+
+#[test]
+fn handles_as_identity_keys() {
+    let a = RcCellOpt::new("a");
+    let b = RcCellOpt::new("b");
+    let mut names = HashMap::new();
+    names.insert(a.as_ptr(), "first");
+    names.insert(b.as_ptr(), "second");
+    assert_eq!(names[&a.clone().as_ptr()], "first");
+    assert_eq!(names[&b.downgrade().as_ptr()], "second");
+    assert!(!a.ptr_eq(&b));
+    assert!(a.downgrade().ptr_eq(&a.downgrade()));
+    assert!(!a.downgrade().ptr_eq(&b.downgrade()));
+}
+
+#[test]
+fn debug() {
+    let strong = RcCellOpt::new(1);
+    assert_eq!(format!("{:?}", strong), "RcCellOpt(Some(1))");
+    assert_eq!(format!("{:?}", strong.downgrade()), "WeakCellOpt(Some(1))");
+}
@@ -0,0 +1,71 @@
+use cellopt::{CellOpt, Error};
+
+#[test]
+fn reentrant_take_reports_borrowed() {
+    let cell = CellOpt::new(1);
+    cell.apply_then_restore(|_| {
+        assert!(matches!(cell.take(), Err(Error::Borrowed)));
+        assert!(matches!(
+            cell.apply_then_restore(|_| ()),
+            Err(Error::Borrowed)
+        ));
+        assert!(matches!(cell.clone_inner(), Err(Error::Borrowed)));
+    })
+    .unwrap();
+    assert_eq!(cell.force_take(), 1);
+    assert!(matches!(cell.take(), Err(Error::Empty)));
+}
+
+#[test]
+fn is_occupied_while_lent() {
+    let cell = CellOpt::new(1);
+    cell.apply_then_restore(|_| assert!(cell.is_occupied()))
+        .unwrap();
+    cell.apply_and_update(|x| {
+        assert!(cell.is_occupied());
+        x
+    })
+    .unwrap();
+}
+
+#[test]
+fn insert_and_overwrite_rejected_while_lent() {
+    let cell = CellOpt::new(1);
+    cell.apply_then_restore(|_| {
+        let err = cell.insert(2).unwrap_err();
+        assert!(matches!(err.err, Error::Borrowed));
+        assert_eq!(err.insert_try, 2);
+        let err = cell.overwrite(3).unwrap_err();
+        assert!(matches!(err.err, Error::Borrowed));
+        assert_eq!(err.insert_try, 3);
+    })
+    .unwrap();
+    assert_eq!(cell.force_take(), 1);
+}
+
+#[test]
+fn debug_while_lent() {
+    let cell = CellOpt::new(1);
+    assert_eq!(format!("{:?}", cell), "CellOpt(Some(1))");
+    cell.apply_then_restore(|_| assert_eq!(format!("{:?}", cell), "CellOpt(<in use>)"))
+        .unwrap();
+    cell.take().unwrap();
+    assert_eq!(format!("{:?}", cell), "CellOpt(None)");
+}
+
+#[test]
+fn clone_while_lent_panics() {
+    #[derive(Clone)]
+    struct Parent {
+        cell: CellOpt<i32>,
+    }
+
+    let parent = Parent {
+        cell: CellOpt::new(1),
+    };
+    let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+        parent.cell.apply_then_restore(|_| parent.clone())
+    }));
+    assert!(result.is_err());
+    assert_eq!(parent.clone().cell.clone_inner(), Ok(1));
+}
@@ -0,0 +1,100 @@
+#![cfg(feature = "serde")]
+
+use cellopt::{CellOpt, Error};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Serialize, Deserialize)]
+struct Config {
+    name: String,
+    limit: CellOpt<u32>,
+    tags: CellOpt<Vec<String>>,
+}
+
+#[test]
+fn json_round_trip() {
+    let config = Config {
+        name: String::from("a"),
+        limit: CellOpt::new(3),
+        tags: CellOpt::default(),
+    };
+    let json = serde_json::to_string(&config).unwrap();
+    assert_eq!(json, r#"{"name":"a","limit":3,"tags":null}"#);
+    let back: Config = serde_json::from_str(&json).unwrap();
+    assert_eq!(back.name, "a");
+    assert_eq!(back.limit.force_take(), 3);
+    assert!(!back.tags.is_occupied());
+}
+
+#[test]
+fn serializes_like_option() {
+    let cell = CellOpt::new(vec![1, 2]);
+    assert_eq!(
+        serde_json::to_value(&cell).unwrap(),
+        serde_json::to_value(Some(vec![1, 2])).unwrap()
+    );
+    assert_eq!(
+        bincode::serialize(&cell).unwrap(),
+        bincode::serialize(&Some(vec![1, 2])).unwrap()
+    );
+    // Serialization goes through `&self` and leaves the value in place.
+    assert_eq!(cell.force_take(), vec![1, 2]);
+}
+
+#[test]
+fn bincode_round_trip() {
+    for cell in [CellOpt::new(String::from("x")), CellOpt::default()] {
+        let bytes = bincode::serialize(&cell).unwrap();
+        let back: CellOpt<String> = bincode::deserialize(&bytes).unwrap();
+        assert_eq!(back.clone_inner().ok(), cell.clone_inner().ok());
+    }
+}
+
+#[test]
+fn mid_lend_serialization_fails() {
+    let cell = CellOpt::new(1);
+    cell.apply_then_restore(|_| {
+        let err = serde_json::to_string(&cell).unwrap_err();
+        assert_eq!(err.to_string(), Error::Borrowed.to_string());
+        assert!(bincode::serialize(&cell).is_err());
+    })
+    .unwrap();
+    assert_eq!(serde_json::to_string(&cell).unwrap(), "1");
+}
+
+#[cfg(feature = "alloc")]
+#[test]
+fn boxed_matches_cellopt() {
+    use cellopt::CellOptBox;
+
+    for value in [Some(vec![1, 2]), None] {
+        let boxed = CellOptBox::<Vec<i32>>::from(value.clone());
+        let json = serde_json::to_string(&boxed).unwrap();
+        assert_eq!(
+            json,
+            serde_json::to_string(&CellOpt::<Vec<i32>>::from(value.clone())).unwrap()
+        );
+        let back: CellOptBox<Vec<i32>> = serde_json::from_str(&json).unwrap();
+        assert_eq!(back.into_inner(), value);
+    }
+    let cell = CellOptBox::new(1);
+    cell.apply_then_restore(|_| assert!(serde_json::to_string(&cell).is_err()))
+        .unwrap();
+}
+
+#[test]
+fn error_round_trip() {
+    for err in [
+        Error::Occupied,
+        Error::Empty,
+        Error::Borrowed,
+        Error::Poisoned,
+        Error::Dropped,
+        Error::Stale,
+    ] {
+        let json = serde_json::to_string(&err).unwrap();
+        assert_eq!(serde_json::from_str::<Error>(&json).unwrap(), err);
+        let bytes = bincode::serialize(&err).unwrap();
+        assert_eq!(bincode::deserialize::<Error>(&bytes).unwrap(), err);
+    }
+    assert_eq!(serde_json::to_string(&Error::Empty).unwrap(), r#""Empty""#);
+}
@@ -0,0 +1,206 @@
+#![cfg(feature = "alloc")]
+
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use cellopt::signal::{Event, Outcome, Signal, Subscription};
+use cellopt::Error;
+
+type Log = Rc<RefCell<Vec<Event<i32>>>>;
+
+fn record(signal: &Signal<i32>) -> (Log, Subscription<i32>) {
+    let log = Log::default();
+    let sink = log.clone();
+    let subscription = signal.subscribe(move |event| sink.borrow_mut().push(event.cloned()));
+    (log, subscription)
+}
+
+#[test]
+fn notifies_each_mutation() {
+    let signal = Signal::default();
+    let (log, _subscription) = record(&signal);
+
+    signal.insert(1).unwrap();
+    assert!(signal.insert(2).is_err());
+    signal.overwrite(3).unwrap();
+    signal.update(|v| v * 2).unwrap();
+    assert_eq!(signal.take(), Ok(6));
+    assert_eq!(signal.take(), Err(Error::Empty));
+    signal.insert(7).unwrap();
+    signal.clear();
+
+    assert_eq!(
+        *log.borrow(),
+        [
+            Event::Inserted(1),
+            Event::Overwritten(3),
+            Event::Updated(6),
+            Event::Taken(6),
+            Event::Inserted(7),
+            Event::Taken(7),
+        ]
+    );
+}
+
+#[test]
+fn event_helpers() {
+    let event = Event::Updated(&String::from("a"));
+    assert_eq!(event.cloned(), Event::Updated(String::from("a")));
+    assert_eq!(event.map(String::len), Event::Updated(1));
+    assert_eq!(Event::Taken(4).value(), 4);
+}
+
+#[test]
+fn dropping_subscription_unsubscribes() {
+    let signal = Signal::default();
+    let (log, subscription) = record(&signal);
+    signal.insert(1).unwrap();
+    drop(subscription);
+    signal.overwrite(2).unwrap();
+    assert_eq!(*log.borrow(), [Event::Inserted(1)]);
+}
+
+#[test]
+fn subscription_outlives_signal() {
+    let signal = Signal::new(1);
+    let (_log, subscription) = record(&signal);
+    drop(signal);
+    drop(subscription);
+}
+
+#[test]
+fn mutations_from_callbacks_are_queued() {
+    let signal = Rc::new(Signal::default());
+    let (log, _subscription) = record(&signal);
+
+    let weak = Rc::downgrade(&signal);
+    let _echo = signal.subscribe(move |event| {
+        let signal = weak.upgrade().unwrap();
+        match event {
+            Event::Inserted(&v) if v < 3 => {
+                // Reentrant reads and value-returning mutations are refused.
+                assert_eq!(signal.take(), Err(Error::Borrowed));
+                assert_eq!(signal.update(|v| v), Err(Error::Borrowed));
+                assert_eq!(signal.overwrite(v + 10).ok(), Some(Outcome::Queued));
+            }
+            Event::Overwritten(&v) if v < 20 => {
+                assert_eq!(signal.clear(), Outcome::Queued);
+                assert_eq!(signal.insert(v - 9).ok(), Some(Outcome::Queued));
+            }
+            _ => {}
+        }
+    });
+
+    assert_eq!(signal.insert(1).ok(), Some(Outcome::Applied));
+    assert_eq!(
+        *log.borrow(),
+        [
+            Event::Inserted(1),
+            Event::Overwritten(11),
+            Event::Taken(11),
+            Event::Inserted(2),
+            Event::Overwritten(12),
+            Event::Taken(12),
+            Event::Inserted(3),
+        ]
+    );
+    assert_eq!(signal.clone_inner(), Ok(3));
+}
+
+#[test]
+fn queued_insert_is_checked_against_future_state() {
+    let signal = Rc::new(Signal::new(0));
+    let weak = Rc::downgrade(&signal);
+    let _subscription = signal.subscribe(move |event| {
+        let signal = weak.upgrade().unwrap();
+        if let Event::Overwritten(&1) = event {
+            // Still occupied: the value comes straight back.
+            let err = signal.insert(99).unwrap_err();
+            assert_eq!((err.insert_try, err.err), (99, Error::Occupied));
+            // Empty once the queued clear has run.
+            assert_eq!(signal.clear(), Outcome::Queued);
+            assert_eq!(signal.insert(2).ok(), Some(Outcome::Queued));
+            // Occupied again after the queued insert.
+            let err = signal.insert(3).unwrap_err();
+            assert_eq!((err.insert_try, err.err), (3, Error::Occupied));
+        }
+    });
+
+    signal.overwrite(1).unwrap();
+    assert_eq!(signal.clone_inner(), Ok(2));
+    assert!(signal.take_rejected().is_empty());
+}
+
+#[test]
+fn queued_values_survive_a_panicking_callback() {
+    let signal = Rc::new(Signal::default());
+    let weak = Rc::downgrade(&signal);
+    let _subscription = signal.subscribe(move |event| {
+        if let Event::Taken(&1) = event {
+            let signal = weak.upgrade().unwrap();
+            assert_eq!(signal.insert(5).ok(), Some(Outcome::Queued));
+            panic!("boom");
+        }
+    });
+
+    signal.insert(1).unwrap();
+    let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| signal.take()));
+    assert!(result.is_err());
+    // The queued insert of 5 runs with the next mutation, which fills the
+    // signal first, so 5 is kept rather than dropped.
+    signal.insert(7).unwrap();
+    assert_eq!(signal.clone_inner(), Ok(7));
+    assert_eq!(signal.take_rejected(), [5]);
+    assert!(signal.take_rejected().is_empty());
+}
+
+#[test]
+fn subscribe_and_unsubscribe_during_dispatch() {
+    let signal = Rc::new(Signal::default());
+    let victim: Rc<RefCell<Option<Subscription<i32>>>> = Default::default();
+    let late: Rc<RefCell<Option<Subscription<i32>>>> = Default::default();
+    let late_log = Log::default();
+
+    // Subscribed first, so it runs before `victim` on every dispatch.
+    let weak = Rc::downgrade(&signal);
+    let (victim_slot, late_slot, sink) = (victim.clone(), late.clone(), late_log.clone());
+    let _controller = signal.subscribe(move |_| {
+        victim_slot.borrow_mut().take();
+        if late_slot.borrow().is_none() {
+            let sink = sink.clone();
+            let subscription = weak
+                .upgrade()
+                .unwrap()
+                .subscribe(move |event| sink.borrow_mut().push(event.cloned()));
+            *late_slot.borrow_mut() = Some(subscription);
+        }
+    });
+    let (victim_log, subscription) = record(&signal);
+    *victim.borrow_mut() = Some(subscription);
+
+    signal.insert(1).unwrap();
+    signal.overwrite(2).unwrap();
+
+    // Unsubscribed before its turn in the first dispatch.
+    assert!(victim_log.borrow().is_empty());
+    // Subscribed during the first dispatch, so it only sees the second.
+    assert_eq!(*late_log.borrow(), [Event::Overwritten(2)]);
+}
+
+#[test]
+fn panicking_callback_keeps_subscribers() {
+    let signal = Signal::default();
+    let (log, _subscription) = record(&signal);
+    let _panicky = signal.subscribe(|event| {
+        if let Event::Inserted(_) = event {
+            panic!("boom");
+        }
+    });
+
+    let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| signal.insert(1)));
+    assert!(result.is_err());
+    assert_eq!(signal.clone_inner(), Ok(1));
+
+    signal.overwrite(2).unwrap();
+    assert_eq!(*log.borrow(), [Event::Inserted(1), Event::Overwritten(2)]);
+}
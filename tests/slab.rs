@@ -0,0 +1,127 @@
+#![cfg(all(feature = "alloc", target_has_atomic = "ptr"))]
+
+use std::collections::HashSet;
+
+use cellopt::slab::CellSlab;
+use cellopt::Error;
+
+#[test]
+fn insert_take_and_access() {
+    let slab = CellSlab::new();
+    let a = slab.insert(String::from("a"));
+    let b = slab.insert(String::from("b"));
+    assert_ne!(a, b);
+    assert_eq!(slab.len(), 2);
+    assert_eq!(slab.apply_then_restore(a, |s| s.clone()), Ok("a".into()));
+    slab.map_in_place(b, |s| s.push('!')).unwrap();
+    assert_eq!(slab.take(b).as_deref(), Ok("b!"));
+    assert_eq!(slab.len(), 1);
+    assert!(slab.contains(a) && !slab.contains(b));
+}
+
+#[test]
+fn stale_handles_do_not_reach_reused_slots() {
+    let slab = CellSlab::new();
+    let old = slab.insert(1);
+    assert_eq!(slab.take(old), Ok(1));
+    let new = slab.insert(2);
+    assert_ne!(old, new);
+    assert_eq!(slab.take(old), Err(Error::Stale));
+    assert_eq!(slab.apply_then_restore(old, |x| *x), Err(Error::Stale));
+    assert_eq!(slab.map_in_place(old, |x| *x = 0), Err(Error::Stale));
+    assert_eq!(slab.take(new), Ok(2));
+    assert_eq!(slab.take(new), Err(Error::Stale));
+}
+
+#[test]
+fn handles_from_another_slab_are_stale() {
+    let (a, b) = (CellSlab::new(), CellSlab::new());
+    let from_a = a.insert("from a");
+    let from_b = b.insert("from b");
+    // Same index and generation, different slabs.
+    assert_eq!(b.take(from_a), Err(Error::Stale));
+    assert_eq!(a.apply_then_restore(from_b, |s| *s), Err(Error::Stale));
+    assert!(!a.contains(from_b));
+    assert_eq!(
+        (a.take(from_a), b.take(from_b)),
+        (Ok("from a"), Ok("from b"))
+    );
+
+    let big = CellSlab::new();
+    let handles: Vec<_> = (0..100).map(|i| big.insert(i)).collect();
+    let small = CellSlab::<i32>::new();
+    assert_eq!(small.take(handles[99]), Err(Error::Stale));
+}
+
+#[test]
+fn grows_while_values_are_lent() {
+    let slab = CellSlab::new();
+    let first = slab.insert(0usize);
+    let inserted = slab
+        .apply_then_restore(first, |value| {
+            let handles: Vec<_> = (1..200).map(|i| slab.insert(i + value)).collect();
+            // The lent value is still readable after the slab has grown.
+            assert_eq!(*value, 0);
+            handles
+        })
+        .unwrap();
+    assert_eq!(slab.len(), 200);
+    for (i, handle) in inserted.into_iter().enumerate() {
+        assert_eq!(slab.take(handle), Ok(i + 1));
+    }
+    assert_eq!(slab.take(first), Ok(0));
+    assert!(slab.is_empty());
+}
+
+#[test]
+fn reentrant_access_to_the_lent_value() {
+    let slab = CellSlab::new();
+    let handle = slab.insert(1);
+    let other = slab.insert(2);
+    slab.apply_then_restore(handle, |_| {
+        assert_eq!(slab.take(handle), Err(Error::Borrowed));
+        assert_eq!(
+            slab.apply_then_restore(handle, |x| *x),
+            Err(Error::Borrowed)
+        );
+        assert_eq!(slab.take(other), Ok(2));
+    })
+    .unwrap();
+    // A failed take leaves the handle valid.
+    assert!(slab.contains(handle));
+    assert_eq!(slab.take(handle), Ok(1));
+}
+
+#[test]
+fn reuses_freed_slots() {
+    let slab = CellSlab::new();
+    let handles: Vec<_> = (0..40).map(|i| slab.insert(i)).collect();
+    for &handle in &handles {
+        slab.take(handle).unwrap();
+    }
+    let again: HashSet<_> = (0..40).map(|i| slab.insert(i)).collect();
+    assert_eq!(again.len(), 40);
+    assert!(handles.iter().all(|handle| !again.contains(handle)));
+    assert_eq!(slab.len(), 40);
+}
+
+#[test]
+fn drops_remaining_values() {
+    use std::rc::Rc;
+
+    let counter = Rc::new(());
+    let slab = CellSlab::new();
+    for _ in 0..50 {
+        slab.insert(counter.clone());
+    }
+    let handle = slab.insert(counter.clone());
+    drop(slab.take(handle));
+    assert_eq!(Rc::strong_count(&counter), 51);
+    drop(slab);
+    assert_eq!(Rc::strong_count(&counter), 1);
+}
+
+#[test]
+fn stale_error_displays() {
+    assert_eq!(Error::Stale.to_string(), "slab handle is stale");
+}
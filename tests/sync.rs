@@ -0,0 +1,137 @@
+#![cfg(feature = "std")]
+
+use std::panic::{catch_unwind, AssertUnwindSafe};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Barrier;
+use std::thread;
+
+use cellopt::{Error, SyncCellOpt};
+
+#[test]
+fn single_thread_api() {
+    let cell = SyncCellOpt::new(1);
+    assert!(cell.is_occupied());
+    assert!(matches!(cell.insert(2).unwrap_err().err, Error::Occupied));
+    assert_eq!(cell.apply_then_restore(|x| x + 1).unwrap(), 2);
+    cell.apply_and_update(|x| x * 10).unwrap();
+    assert_eq!(cell.clone_inner().unwrap(), 10);
+    assert!(cell.overwrite(5).is_ok());
+    assert_eq!(cell.force_take(), 5);
+    assert!(matches!(cell.take(), Err(Error::Empty)));
+    assert!(cell.insert(3).is_ok());
+    assert_eq!(format!("{:?}", cell), "SyncCellOpt(Some(3))");
+}
+
+#[test]
+fn reentrant_access_reports_borrowed() {
+    let cell = SyncCellOpt::new(1);
+    cell.apply_then_restore(|_| {
+        assert!(cell.is_occupied());
+        assert!(matches!(cell.take(), Err(Error::Borrowed)));
+        assert!(matches!(
+            cell.overwrite(2).unwrap_err().err,
+            Error::Borrowed
+        ));
+    })
+    .unwrap();
+    assert_eq!(cell.force_take(), 1);
+}
+
+#[test]
+fn other_threads_wait_for_lent_value() {
+    let cell = SyncCellOpt::new(1);
+    let lent = Barrier::new(2);
+    thread::scope(|s| {
+        s.spawn(|| {
+            cell.apply_then_restore(|_| {
+                lent.wait();
+                // Give the other thread time to start waiting.
+                thread::sleep(std::time::Duration::from_millis(50));
+            })
+            .unwrap();
+        });
+        s.spawn(|| {
+            lent.wait();
+            assert_eq!(cell.take().unwrap(), 1);
+            assert!(cell.insert(2).is_ok());
+            assert_eq!(cell.apply_then_restore(|x| *x).unwrap(), 2);
+        });
+    });
+    assert_eq!(cell.force_take(), 2);
+}
+
+#[test]
+fn update_panic_poisons() {
+    let cell = SyncCellOpt::new(String::from("state"));
+    let result = catch_unwind(AssertUnwindSafe(|| {
+        let _ = cell.apply_and_update(|_| panic!("boom"));
+    }));
+    assert!(result.is_err());
+    assert!(matches!(cell.take(), Err(Error::Poisoned)));
+    cell.clear_poison();
+    assert!(cell.insert(String::from("fresh")).is_ok());
+    assert_eq!(cell.into_inner().as_deref(), Some("fresh"));
+}
+
+#[test]
+fn exactly_one_thread_takes() {
+    let cell = SyncCellOpt::new(String::from("prize"));
+    let winners = AtomicUsize::new(0);
+    let barrier = Barrier::new(8);
+    thread::scope(|s| {
+        for _ in 0..8 {
+            s.spawn(|| {
+                barrier.wait();
+                if cell.take().is_ok() {
+                    winners.fetch_add(1, Ordering::Relaxed);
+                }
+            });
+        }
+    });
+    assert_eq!(winners.load(Ordering::Relaxed), 1);
+    assert!(!cell.is_occupied());
+}
+
+#[test]
+fn concurrent_updates_are_not_lost() {
+    let cell = SyncCellOpt::new(0usize);
+    let updates = AtomicUsize::new(0);
+    thread::scope(|s| {
+        for _ in 0..4 {
+            s.spawn(|| {
+                for _ in 0..1000 {
+                    // Waits while another thread has the value lent out.
+                    cell.apply_and_update(|x| x + 1).unwrap();
+                    updates.fetch_add(1, Ordering::Relaxed);
+                }
+            });
+        }
+    });
+    assert_eq!(cell.force_take(), updates.load(Ordering::Relaxed));
+}
+
+#[test]
+fn producer_consumer_handoff() {
+    let cell = SyncCellOpt::default();
+    thread::scope(|s| {
+        s.spawn(|| {
+            for i in 0..100 {
+                while cell.insert(i).is_err() {
+                    thread::yield_now();
+                }
+            }
+        });
+        s.spawn(|| {
+            for i in 0..100 {
+                loop {
+                    if let Ok(value) = cell.take() {
+                        assert_eq!(value, i);
+                        break;
+                    }
+                    thread::yield_now();
+                }
+            }
+        });
+    });
+    assert!(!cell.is_occupied());
+}
@@ -0,0 +1,138 @@
+use cellopt::slice;
+use cellopt::{CellOpt, Error};
+
+fn cells(values: &[Option<i32>]) -> Vec<CellOpt<i32>> {
+    values.iter().map(|&v| CellOpt::from(v)).collect()
+}
+
+fn contents(cells: &[CellOpt<i32>]) -> Vec<Option<i32>> {
+    cells.iter().map(|c| c.clone_inner().ok()).collect()
+}
+
+#[test]
+fn swap_exchanges_contents() {
+    let (a, b) = (CellOpt::new(1), CellOpt::default());
+    a.swap(&b).unwrap();
+    assert_eq!((a.take(), b.take()), (Err(Error::Empty), Ok(1)));
+
+    let c = CellOpt::new(2);
+    c.swap(&c).unwrap();
+    assert_eq!(c.clone_inner(), Ok(2));
+}
+
+#[test]
+fn swap_refuses_lent_cells() {
+    let (a, b) = (CellOpt::new(1), CellOpt::new(2));
+    a.apply_then_restore(|_| {
+        assert_eq!(a.swap(&b), Err(Error::Borrowed));
+        assert_eq!(b.swap(&a), Err(Error::Borrowed));
+        assert_eq!(a.swap(&a), Err(Error::Borrowed));
+    })
+    .unwrap();
+    assert_eq!((a.take(), b.take()), (Ok(1), Ok(2)));
+}
+
+#[test]
+fn move_to_keeps_both_values_when_occupied() {
+    let (a, b) = (CellOpt::new(1), CellOpt::new(2));
+    assert_eq!(a.move_to(&b), Err(Error::Occupied));
+    assert_eq!(a.move_to(&a), Err(Error::Occupied));
+    assert_eq!((a.clone_inner(), b.clone_inner()), (Ok(1), Ok(2)));
+
+    b.take().unwrap();
+    a.move_to(&b).unwrap();
+    assert_eq!((a.take(), b.clone_inner()), (Err(Error::Empty), Ok(1)));
+    assert_eq!(a.move_to(&a), Err(Error::Empty));
+}
+
+#[test]
+fn take_from_moves_into_self() {
+    let (a, b) = (CellOpt::default(), CellOpt::new(1));
+    a.take_from(&b).unwrap();
+    assert_eq!((a.clone_inner(), b.take()), (Ok(1), Err(Error::Empty)));
+    assert_eq!(a.take_from(&b), Err(Error::Occupied));
+    assert_eq!(b.take_from(&CellOpt::default()), Err(Error::Empty));
+    a.apply_then_restore(|_| assert_eq!(b.take_from(&a), Err(Error::Borrowed)))
+        .unwrap();
+    assert_eq!(a.clone_inner(), Ok(1));
+}
+
+#[test]
+fn rotations_match_slices() {
+    let values = [Some(1), None, Some(3), Some(4), None];
+    for mid in 0..=values.len() {
+        let rotated = cells(&values);
+        slice::rotate_left(&rotated, mid).unwrap();
+        let mut expected = values.to_vec();
+        expected.rotate_left(mid);
+        assert_eq!(contents(&rotated), expected);
+
+        let rotated = cells(&values);
+        slice::rotate_right(&rotated, mid).unwrap();
+        let mut expected = values.to_vec();
+        expected.rotate_right(mid);
+        assert_eq!(contents(&rotated), expected);
+    }
+    slice::rotate_left::<i32>(&[], 0).unwrap();
+}
+
+#[test]
+#[should_panic]
+fn rotate_past_end_panics() {
+    let _ = slice::rotate_left(&cells(&[Some(1)]), 2);
+}
+
+#[test]
+fn swap_indices_exchanges() {
+    let row = cells(&[Some(1), None, Some(3)]);
+    slice::swap_indices(&row, 0, 1).unwrap();
+    slice::swap_indices(&row, 2, 2).unwrap();
+    assert_eq!(contents(&row), [None, Some(1), Some(3)]);
+}
+
+#[test]
+fn lent_cell_blocks_slice_operations() {
+    let row = cells(&[Some(1), Some(2), Some(3)]);
+    row[1]
+        .apply_then_restore(|_| {
+            assert_eq!(slice::rotate_left(&row, 1), Err(Error::Borrowed));
+            assert_eq!(slice::rotate_right(&row, 1), Err(Error::Borrowed));
+            assert_eq!(slice::swap_indices(&row, 0, 1), Err(Error::Borrowed));
+            #[cfg(feature = "alloc")]
+            assert_eq!(slice::permute(&row, &[2, 0, 1]), Err(Error::Borrowed));
+        })
+        .unwrap();
+    assert_eq!(contents(&row), [Some(1), Some(2), Some(3)]);
+}
+
+#[cfg(feature = "alloc")]
+#[test]
+fn permute_follows_indices() {
+    let values = [Some(10), Some(11), None, Some(13), Some(14), Some(15)];
+    for indices in [
+        [0, 1, 2, 3, 4, 5],
+        [5, 4, 3, 2, 1, 0],
+        [1, 2, 3, 4, 5, 0],
+        [2, 0, 1, 4, 5, 3],
+        [0, 3, 2, 1, 5, 4],
+    ] {
+        let row = cells(&values);
+        slice::permute(&row, &indices).unwrap();
+        let expected: Vec<_> = indices.iter().map(|&i| values[i]).collect();
+        assert_eq!(contents(&row), expected);
+    }
+}
+
+#[cfg(feature = "alloc")]
+#[test]
+#[should_panic(expected = "not a permutation")]
+fn permute_rejects_repeated_index() {
+    let _ = slice::permute(&cells(&[Some(1), Some(2)]), &[1, 1]);
+}
+
+#[cfg(feature = "alloc")]
+#[test]
+#[should_panic(expected = "wrong number")]
+fn permute_rejects_short_list() {
+    let _ = slice::permute(&cells(&[Some(1), Some(2)]), &[0]);
+}
@@ -0,0 +1,271 @@
+#![cfg(feature = "alloc")]
+
+use std::cell::Cell;
+use std::panic::{catch_unwind, AssertUnwindSafe};
+
+use cellopt::txn::Transaction;
+use cellopt::{CellOpt, Error};
+
+fn poisoned() -> CellOpt<i32> {
+    let cell = CellOpt::new(0);
+    let _ = catch_unwind(AssertUnwindSafe(|| {
+        cell.update(|_| -> (i32, ()) { panic!() })
+    }));
+    assert!(cell.is_poisoned());
+    cell
+}
+
+#[test]
+fn commit_keeps_changes() {
+    let (a, b, c) = (CellOpt::new(1), CellOpt::new(2), CellOpt::default());
+    let txn = Transaction::new();
+    let sum = *txn.take(&a).unwrap() + *txn.take(&b).unwrap();
+    txn.insert(&c, sum).unwrap();
+    assert_eq!(txn.len(), 3);
+    // Nothing is applied until commit.
+    assert_eq!(a.take(), Err(Error::Borrowed));
+    assert_eq!(c.take(), Err(Error::Borrowed));
+    txn.commit();
+    assert!(!a.is_occupied() && !b.is_occupied());
+    assert_eq!(c.clone_inner(), Ok(3));
+}
+
+/// Neither `Clone` nor `Copy`.
+#[derive(Debug, PartialEq)]
+struct Buffer(Vec<u8>);
+
+#[test]
+fn claims_own_taken_values() {
+    let (a, b, c) = (
+        CellOpt::new(Buffer(vec![1])),
+        CellOpt::new(Buffer(vec![2])),
+        CellOpt::default(),
+    );
+    let txn = Transaction::new();
+    let first = txn.take(&a).unwrap();
+    assert_eq!(first.0, [1]);
+    let first = first.claim();
+    let second = txn.take(&b).unwrap().claim();
+    txn.commit();
+    let (mut first, second) = (first.into_inner().unwrap(), second.into_inner().unwrap());
+    first.0.extend(second.0);
+    c.insert(first).unwrap();
+    assert!(!a.is_occupied() && !b.is_occupied());
+    assert_eq!(c.take(), Ok(Buffer(vec![1, 2])));
+
+    // Nothing is handed out on rollback, or before the commit.
+    a.insert(Buffer(vec![3])).unwrap();
+    let txn = Transaction::new();
+    let claim = txn.take(&a).unwrap().claim();
+    drop(txn);
+    assert_eq!(claim.into_inner(), None);
+    let txn = Transaction::new();
+    let early = txn.take(&a).unwrap().claim();
+    assert_eq!(early.into_inner(), None);
+    txn.commit();
+    assert!(!a.is_occupied());
+}
+
+#[test]
+fn rollback_and_drop_restore() {
+    let (a, c) = (CellOpt::new(1), CellOpt::default());
+    let txn = Transaction::new();
+    txn.take(&a).unwrap();
+    txn.insert(&c, 5).unwrap();
+    txn.rollback();
+    assert_eq!((a.clone_inner(), c.take()), (Ok(1), Err(Error::Empty)));
+
+    let txn = Transaction::new();
+    txn.take(&a).unwrap();
+    txn.insert(&c, 5).unwrap();
+    drop(txn);
+    assert_eq!((a.clone_inner(), c.take()), (Ok(1), Err(Error::Empty)));
+}
+
+#[test]
+fn mixes_types() {
+    let (name, count, flag) = (
+        CellOpt::new(String::from("x")),
+        CellOpt::new(3usize),
+        CellOpt::<bool>::default(),
+    );
+    let txn = Transaction::new();
+    let len = txn.take(&name).unwrap().len();
+    let count_now = *txn.take(&count).unwrap();
+    txn.insert(&flag, len == count_now).unwrap();
+    drop(txn);
+    assert_eq!(name.clone_inner().as_deref(), Ok("x"));
+    assert_eq!(count.clone_inner(), Ok(3));
+    assert!(!flag.is_occupied());
+}
+
+#[test]
+fn failing_take_leaves_earlier_steps_to_rollback() {
+    let lent = CellOpt::new(0);
+    let poison = poisoned();
+    for (failing, expected) in [
+        (&CellOpt::default(), Error::Empty),
+        (&poison, Error::Poisoned),
+        (&lent, Error::Borrowed),
+    ] {
+        let (a, c) = (CellOpt::new(1), CellOpt::default());
+        let result = lent.apply_then_restore(|_| {
+            let txn = Transaction::new();
+            txn.take(&a).unwrap();
+            txn.insert(&c, 9).unwrap();
+            let err = txn.take(failing).unwrap_err();
+            assert_eq!(txn.len(), 2);
+            err
+        });
+        assert_eq!(result, Ok(expected));
+        assert_eq!(a.clone_inner(), Ok(1));
+        assert!(!c.is_occupied());
+    }
+    assert!(poison.is_poisoned());
+}
+
+#[test]
+fn failing_insert_returns_value_and_rolls_back() {
+    let lent = CellOpt::new(0);
+    let poison = poisoned();
+    for (failing, expected) in [
+        (&CellOpt::new(7), Error::Occupied),
+        (&poison, Error::Poisoned),
+        (&lent, Error::Borrowed),
+    ] {
+        let a = CellOpt::new(1);
+        lent.apply_then_restore(|_| {
+            let txn = Transaction::new();
+            let value = *txn.take(&a).unwrap();
+            let err = txn.insert(failing, value + 1).unwrap_err();
+            assert_eq!((err.insert_try, err.err), (2, expected));
+            assert_eq!(txn.len(), 1);
+        })
+        .unwrap();
+        assert_eq!(a.clone_inner(), Ok(1));
+    }
+}
+
+#[test]
+fn failing_transfer_leaves_both_cells() {
+    let lent = CellOpt::new(0);
+    let full = CellOpt::new(2);
+    let empty = CellOpt::default();
+    let src = CellOpt::new(1);
+    lent.apply_then_restore(|_| {
+        let txn = Transaction::new();
+        assert_eq!(txn.transfer(&src, &full), Err(Error::Occupied));
+        assert_eq!(txn.transfer(&src, &lent), Err(Error::Borrowed));
+        assert_eq!(txn.transfer(&lent, &empty), Err(Error::Borrowed));
+        assert_eq!(txn.transfer(&empty, &full), Err(Error::Occupied));
+        assert_eq!(txn.transfer(&src, &src), Err(Error::Occupied));
+        assert!(txn.is_empty());
+    })
+    .unwrap();
+    assert_eq!((src.clone_inner(), full.clone_inner()), (Ok(1), Ok(2)));
+    assert!(!empty.is_occupied());
+
+    let other = CellOpt::default();
+    let txn = Transaction::new();
+    assert_eq!(txn.transfer(&empty, &other), Err(Error::Empty));
+    assert!(txn.is_empty());
+}
+
+#[test]
+fn transfer_rolls_back() {
+    let (a, b) = (CellOpt::new(1), CellOpt::default());
+    let txn = Transaction::new();
+    txn.transfer(&a, &b).unwrap();
+    assert_eq!(a.clone_inner(), Err(Error::Borrowed));
+    assert_eq!(b.clone_inner(), Err(Error::Borrowed));
+    drop(txn);
+    assert_eq!((a.clone_inner(), b.take()), (Ok(1), Err(Error::Empty)));
+
+    let txn = Transaction::new();
+    txn.transfer(&a, &b).unwrap();
+    txn.commit();
+    assert_eq!((a.take(), b.clone_inner()), (Err(Error::Empty), Ok(1)));
+}
+
+#[test]
+fn one_operation_per_cell() {
+    let (a, b) = (CellOpt::new(1), CellOpt::default());
+    let txn = Transaction::new();
+    txn.take(&a).unwrap();
+    assert_eq!(txn.take(&a).err(), Some(Error::Borrowed));
+    let err = txn.insert(&a, 2).unwrap_err();
+    assert_eq!((err.insert_try, err.err), (2, Error::Borrowed));
+    assert_eq!(txn.transfer(&a, &b), Err(Error::Borrowed));
+    txn.insert(&b, 3).unwrap();
+    assert_eq!(txn.transfer(&a, &b), Err(Error::Borrowed));
+    assert_eq!(txn.len(), 2);
+    drop(txn);
+    assert_eq!((a.clone_inner(), b.take()), (Ok(1), Err(Error::Empty)));
+}
+
+#[test]
+fn outside_changes_are_refused_and_nothing_is_lost() {
+    let (a, c) = (CellOpt::new(1), CellOpt::default());
+    let txn = Transaction::new();
+    txn.take(&a).unwrap();
+    txn.insert(&c, 2).unwrap();
+
+    for cell in [&a, &c] {
+        let err = cell.insert(10).unwrap_err();
+        assert_eq!((err.insert_try, err.err), (10, Error::Borrowed));
+        let err = cell.overwrite(11).unwrap_err();
+        assert_eq!((err.insert_try, err.err), (11, Error::Borrowed));
+        assert_eq!(cell.take(), Err(Error::Borrowed));
+        assert_eq!(cell.update(|v| (v, ())), Err(Error::Borrowed));
+        cell.clear_poison();
+        assert!(!cell.is_poisoned());
+    }
+    drop(txn);
+    assert_eq!((a.clone_inner(), c.take()), (Ok(1), Err(Error::Empty)));
+
+    // The same holds on commit.
+    let txn = Transaction::new();
+    txn.take(&a).unwrap();
+    txn.insert(&c, 2).unwrap();
+    assert!(a.insert(10).is_err() && c.overwrite(11).is_err());
+    txn.commit();
+    assert_eq!((a.take(), c.clone_inner()), (Err(Error::Empty), Ok(2)));
+}
+
+#[test]
+fn unwinding_rolls_back() {
+    let (a, c) = (CellOpt::new(1), CellOpt::default());
+    let result = catch_unwind(AssertUnwindSafe(|| {
+        let txn = Transaction::new();
+        txn.take(&a).unwrap();
+        txn.insert(&c, 2).unwrap();
+        panic!("midway");
+    }));
+    assert!(result.is_err());
+    assert_eq!(a.clone_inner(), Ok(1));
+    assert!(!c.is_occupied());
+}
+
+#[test]
+fn commit_drops_taken_values_once() {
+    struct Counted<'a>(&'a Cell<u32>);
+    impl Drop for Counted<'_> {
+        fn drop(&mut self) {
+            self.0.set(self.0.get() + 1);
+        }
+    }
+
+    let drops = Cell::new(0);
+    let (a, b) = (CellOpt::new(Counted(&drops)), CellOpt::default());
+    let txn = Transaction::new();
+    txn.take(&a).unwrap();
+    txn.insert(&b, Counted(&drops)).unwrap();
+    drop(txn);
+    assert_eq!(drops.get(), 1);
+    assert!(a.is_occupied() && !b.is_occupied());
+
+    let txn = Transaction::new();
+    txn.take(&a).unwrap();
+    txn.commit();
+    assert_eq!(drops.get(), 2);
+}
@@ -0,0 +1,100 @@
+use std::panic::{catch_unwind, AssertUnwindSafe};
+
+use cellopt::{CellOpt, Error};
+
+#[derive(Debug, PartialEq)]
+enum State {
+    Idle { jobs: Vec<u32> },
+    Running { job: u32, rest: Vec<u32> },
+}
+
+#[test]
+fn update_returns_side_result() {
+    let cell = CellOpt::new(State::Idle {
+        jobs: vec![1, 2, 3],
+    });
+    let started = cell
+        .update(|state| match state {
+            State::Idle { mut jobs } => {
+                let job = jobs.remove(0);
+                (State::Running { job, rest: jobs }, Some(job))
+            }
+            running => (running, None),
+        })
+        .unwrap();
+    assert_eq!(started, Some(1));
+    assert_eq!(
+        cell.force_take(),
+        State::Running {
+            job: 1,
+            rest: vec![2, 3]
+        }
+    );
+}
+
+#[test]
+fn update_moves_captured_state() {
+    let cell = CellOpt::new(vec![1]);
+    let extra = vec![2, 3];
+    cell.update(move |mut v| {
+        v.extend(extra);
+        (v, ())
+    })
+    .unwrap();
+    assert_eq!(cell.force_take(), vec![1, 2, 3]);
+    assert_eq!(cell.update(|v| (v, ())), Err(Error::Empty));
+}
+
+#[derive(Debug, PartialEq)]
+enum AppError {
+    Cell(Error),
+    TooBig,
+}
+
+impl From<Error> for AppError {
+    fn from(err: Error) -> Self {
+        AppError::Cell(err)
+    }
+}
+
+fn grow(cell: &CellOpt<u32>) -> Result<(), AppError> {
+    cell.try_update(|x| {
+        if x >= 10 {
+            Err((x, AppError::TooBig))
+        } else {
+            Ok(x * 2)
+        }
+    })
+}
+
+#[test]
+fn try_update_restores_on_failure() {
+    let cell = CellOpt::new(4);
+    assert_eq!(grow(&cell), Ok(()));
+    assert_eq!(grow(&cell), Ok(()));
+    assert_eq!(grow(&cell), Err(AppError::TooBig));
+    assert_eq!(cell.clone_inner(), Ok(16));
+    cell.take().unwrap();
+    assert_eq!(grow(&cell), Err(AppError::Cell(Error::Empty)));
+}
+
+#[test]
+fn update_panic_poisons() {
+    let cell = CellOpt::new(1);
+    let result = catch_unwind(AssertUnwindSafe(|| {
+        let _ = cell.update(|_| -> (i32, ()) { panic!("boom") });
+    }));
+    assert!(result.is_err());
+    assert_eq!(cell.take(), Err(Error::Poisoned));
+}
+
+#[test]
+fn apply_then_restore_once_accepts_fn_once() {
+    let cell = CellOpt::new(2);
+    let owned = String::from("x");
+    let s = cell
+        .apply_then_restore_once(move |n| owned.repeat(*n))
+        .unwrap();
+    assert_eq!(s, "xx");
+    assert_eq!(cell.force_take(), 2);
+}
@@ -0,0 +1,131 @@
+#![cfg(feature = "alloc")]
+
+mod common;
+
+use std::rc::Rc;
+use std::task::Poll;
+
+use cellopt::watch::{channel, Closed};
+use cellopt::CellOpt;
+use common::{poll_once, LocalExecutor};
+
+#[test]
+fn receivers_see_each_new_version() {
+    let executor = LocalExecutor::default();
+    let (sender, receiver) = channel(0);
+    let seen = Rc::new(CellOpt::new(Vec::new()));
+    for mut receiver in [receiver.clone(), receiver] {
+        let seen = Rc::clone(&seen);
+        executor.spawn(async move {
+            while receiver.changed().await.is_ok() {
+                let value = receiver.borrow_and_clone().unwrap();
+                seen.map_in_place(|seen| seen.push(value)).unwrap();
+            }
+        });
+    }
+    assert_eq!(executor.run_until_stalled(), 2);
+    sender.send(1).unwrap();
+    assert_eq!(executor.run_until_stalled(), 2);
+    sender.send(2).unwrap();
+    assert_eq!(executor.run_until_stalled(), 2);
+    drop(sender);
+    assert_eq!(executor.run_until_stalled(), 0);
+    assert_eq!(seen.force_take(), vec![1, 1, 2, 2]);
+}
+
+#[test]
+fn intermediate_values_are_skipped() {
+    let (sender, mut receiver) = channel("a");
+    sender.send("b").unwrap();
+    sender.send("c").unwrap();
+    assert_eq!(receiver.has_changed(), Ok(true));
+    let (poll, _) = poll_once(&mut Box::pin(receiver.changed()));
+    assert_eq!(poll, Poll::Ready(Ok(())));
+    assert_eq!(receiver.borrow_and_clone(), Ok("c"));
+    assert_eq!(receiver.has_changed(), Ok(false));
+}
+
+#[test]
+fn per_receiver_seen_version() {
+    let (sender, mut first) = channel(0);
+    sender.send(1).unwrap();
+    let late = sender.subscribe();
+    assert_eq!(first.has_changed(), Ok(true));
+    assert_eq!(late.has_changed(), Ok(false));
+    assert_eq!(first.borrow_and_clone(), Ok(1));
+    assert_eq!(first.has_changed(), Ok(false));
+}
+
+#[test]
+fn wakes_pending_receiver() {
+    let (sender, mut receiver) = channel(0);
+    {
+        let mut changed = Box::pin(receiver.changed());
+        let (poll, flag) = poll_once(&mut changed);
+        assert_eq!(poll, Poll::Pending);
+        sender.send(1).unwrap();
+        assert_eq!(flag.wakes(), 1);
+        assert_eq!(poll_once(&mut changed).0, Poll::Ready(Ok(())));
+    }
+    assert_eq!(receiver.apply_then_restore(|x| x * 10), Ok(10));
+}
+
+#[test]
+fn closed_after_last_value_is_seen() {
+    let (sender, mut receiver) = channel(0);
+    sender.send(1).unwrap();
+    drop(sender);
+    assert_eq!(receiver.has_changed(), Ok(true));
+    assert_eq!(
+        poll_once(&mut Box::pin(receiver.changed())).0,
+        Poll::Ready(Ok(()))
+    );
+    assert_eq!(receiver.has_changed(), Err(Closed));
+    assert_eq!(
+        poll_once(&mut Box::pin(receiver.changed())).0,
+        Poll::Ready(Err(Closed))
+    );
+    // The last value stays readable.
+    assert_eq!(receiver.borrow_and_clone(), Ok(1));
+}
+
+#[test]
+fn send_while_value_is_read_is_rejected() {
+    let (sender, mut receiver) = channel(0);
+    receiver
+        .apply_then_restore(|_| {
+            let err = sender.send(1).unwrap_err();
+            assert_eq!(err.error(), cellopt::Error::Borrowed);
+        })
+        .unwrap();
+    assert_eq!(receiver.has_changed(), Ok(false));
+}
+
+#[cfg(feature = "futures")]
+#[test]
+fn stream() {
+    use std::future::poll_fn;
+    use std::pin::Pin;
+
+    use cellopt::watch::WatchStream;
+    use futures_core::Stream;
+
+    let executor = LocalExecutor::default();
+    let (sender, receiver) = channel(0);
+    let seen = Rc::new(CellOpt::new(Vec::new()));
+    let out = Rc::clone(&seen);
+    executor.spawn(async move {
+        let mut stream = WatchStream::from(receiver);
+        while let Some(value) = poll_fn(|cx| Pin::new(&mut stream).poll_next(cx)).await {
+            out.map_in_place(|seen| seen.push(value)).unwrap();
+        }
+    });
+    executor.run_until_stalled();
+    for value in 1..=3 {
+        sender.send(value).unwrap();
+        executor.run_until_stalled();
+    }
+    drop(sender);
+    assert_eq!(executor.run_until_stalled(), 0);
+    assert_eq!(seen.force_take(), vec![1, 2, 3]);
+}